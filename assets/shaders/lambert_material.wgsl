@@ -1,8 +1,11 @@
 // #import bevy_pbr::forward_io::VertexOutput
 // // we can import items from shader modules in the assets folder with a quoted path
-#import "shaders/util.wgsl"::diffuse
+#import "shaders/util.wgsl"::{diffuse, MAX_LIGHTS}
 
-#import bevy_pbr::mesh_functions::{get_world_from_local, mesh_position_local_to_clip}
+#import bevy_pbr::mesh_functions::{
+    get_world_from_local, mesh_normal_local_to_world, mesh_position_local_to_clip,
+    mesh_position_local_to_world,
+}
 
 // Vertex shader
 struct Vertex {
@@ -21,13 +24,14 @@ struct VertexOutput {
 fn vertex(input: Vertex) -> VertexOutput {
     var output: VertexOutput;
 
-    output.clip_position = mesh_position_local_to_clip(
-        get_world_from_local(input.instance_index),
-        vec4<f32>(input.position, 1.0),
-    );
+    let world_from_local = get_world_from_local(input.instance_index);
+    let position = vec4<f32>(input.position, 1.0);
+
+    output.clip_position = mesh_position_local_to_clip(world_from_local, position);
 
-    output.world_position = input.position;
-    output.world_normal = normalize(input.normal);
+    // The light is placed in world space, so shade there
+    output.world_position = mesh_position_local_to_world(world_from_local, position).xyz;
+    output.world_normal = mesh_normal_local_to_world(input.normal, input.instance_index);
 
     return output;
 }
@@ -38,8 +42,9 @@ struct FragmentInput {
 }
 
 @group(2) @binding(0) var<uniform> color: vec3<f32>;
-@group(2) @binding(1) var<uniform> light_pos: vec3<f32>;
-@group(2) @binding(2) var<uniform> light_int: f32;
+// Position in xyz and intensity in w, of the first `light_count` lights
+@group(2) @binding(1) var<uniform> lights: array<vec4<f32>, MAX_LIGHTS>;
+@group(2) @binding(2) var<uniform> light_count: u32;
 
 @fragment
 fn fragment(input: FragmentInput) -> @location(0) vec4<f32> {
@@ -48,11 +53,14 @@ fn fragment(input: FragmentInput) -> @location(0) vec4<f32> {
     // let diffuse = max(dot(normal, light_dir), 0.0) * light_int;
     // let diff_col = color * diffuse;
 
-    let diff_col = diffuse(input.world_position, 
-                             input.world_normal,
-                             color,
-                             light_pos,
-                             light_int);
+    var diff_col = vec3<f32>(0.0);
+    for (var i = 0u; i < min(light_count, MAX_LIGHTS); i++) {
+        diff_col += diffuse(input.world_position,
+                            input.world_normal,
+                            color,
+                            lights[i].xyz,
+                            lights[i].w);
+    }
 
     return vec4(diff_col, 1.0);
 }
@@ -1,6 +1,9 @@
-// @group(2) @binding(0) var<uniform> pos: vec3<f32>
+#import bevy_pbr::forward_io::VertexOutput
+
+// Unlit, so the marker stays visible whatever lights the scene
+@group(2) @binding(0) var<uniform> color: vec4<f32>;
 
 @fragment
-fn fragment() ->  @location(0) vec4<f32> {
-    return vec4(1.0, 0.87, 0.13, 1.0);
-}
\ No newline at end of file
+fn fragment(input: VertexOutput) -> @location(0) vec4<f32> {
+    return color;
+}
@@ -0,0 +1,100 @@
+// Mapgen4-style rendering: biome colors looked up by elevation and moisture, hillshading from
+// the slope of the terrain, and dark outlines wherever the depth jumps.
+//
+// The outlines compare the depth prepass at neighbouring pixels, so the camera needs a
+// `DepthPrepass`. Water is flattened to sea level on the CPU, so the prepass sees the same
+// surface as this shader.
+
+#import bevy_pbr::mesh_functions::{get_world_from_local, mesh_position_local_to_clip}
+#import bevy_pbr::mesh_view_bindings as view_bindings
+#import bevy_pbr::view_transformations::depth_ndc_to_view_z
+
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+    @location(0) position: vec3<f32>,
+    // (moisture, elevation), both from 0.0 to 1.0
+    @location(2) lookup: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) lookup: vec2<f32>,
+};
+
+@vertex
+fn vertex(input: Vertex) -> VertexOutput {
+    var output: VertexOutput;
+
+    let position = vec4<f32>(input.position, 1.0);
+    let world_from_local = get_world_from_local(input.instance_index);
+
+    output.clip_position = mesh_position_local_to_clip(world_from_local, position);
+    output.world_position = (world_from_local * position).xyz;
+    output.lookup = input.lookup;
+
+    return output;
+}
+
+struct FragmentInput {
+    @builtin(position) frag_coord: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) lookup: vec2<f32>,
+}
+
+// Towards the light, in world space
+@group(2) @binding(0) var<uniform> light_direction: vec3<f32>;
+@group(2) @binding(1) var<uniform> shading_strength: f32;
+@group(2) @binding(2) var<uniform> outline_strength: f32;
+// Depth jump to a neighbouring pixel, in map units, where outlines start
+@group(2) @binding(3) var<uniform> outline_depth: f32;
+@group(2) @binding(4) var colormap: texture_2d<f32>;
+@group(2) @binding(5) var colormap_sampler: sampler;
+
+@fragment
+fn fragment(input: FragmentInput) -> @location(0) vec4<f32> {
+    let color = textureSample(colormap, colormap_sampler, input.lookup).rgb;
+
+    // The surface normal from the screen-space derivatives of the position, facing up
+    var normal = normalize(cross(dpdx(input.world_position), dpdy(input.world_position)));
+    if (normal.y < 0.0) {
+        normal = -normal;
+    }
+
+    // Flat ground keeps its color, slopes facing the light brighten and the others darken
+    let flat_light = normalize(light_direction).y;
+    let light = dot(normal, normalize(light_direction)) - flat_light;
+    let shade = 1.0 + shading_strength * light;
+
+    let edge = smoothstep(outline_depth, 2.0 * outline_depth, depth_jump(input.frag_coord));
+    let outline = 1.0 - outline_strength * edge;
+
+    return vec4<f32>(color * shade * outline, 1.0);
+}
+
+// How much farther from the camera the farthest of the four neighbouring pixels is, like
+// Mapgen4's outline pass. The jump is large where a ridge hides the terrain behind it.
+fn depth_jump(frag_coord: vec4<f32>) -> f32 {
+#ifdef DEPTH_PREPASS
+    let pixel = vec2<i32>(frag_coord.xy);
+    let center = view_z(pixel);
+
+    // View space looks down -z, so farther neighbours have a lower z
+    let horizontal = min(view_z(pixel + vec2<i32>(1, 0)), view_z(pixel - vec2<i32>(1, 0)));
+    let vertical = min(view_z(pixel + vec2<i32>(0, 1)), view_z(pixel - vec2<i32>(0, 1)));
+
+    return center - min(horizontal, vertical);
+#else
+    return 0.0;
+#endif
+}
+
+#ifdef DEPTH_PREPASS
+// View space z of the nearest surface at `pixel`, clamped to the screen
+fn view_z(pixel: vec2<i32>) -> f32 {
+    let size = vec2<i32>(textureDimensions(view_bindings::depth_prepass_texture));
+    let clamped = clamp(pixel, vec2<i32>(0), size - 1);
+
+    return depth_ndc_to_view_z(textureLoad(view_bindings::depth_prepass_texture, clamped, 0));
+}
+#endif
@@ -0,0 +1,73 @@
+#import "shaders/util.wgsl"::{diffuse, MAX_LIGHTS}
+
+#import bevy_pbr::mesh_functions::{
+    get_world_from_local, mesh_normal_local_to_world, mesh_position_local_to_clip,
+    mesh_position_local_to_world,
+}
+#import bevy_pbr::mesh_view_bindings::view
+
+// Vertex shader
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) world_normal: vec3<f32>,
+};
+
+@vertex
+fn vertex(input: Vertex) -> VertexOutput {
+    var output: VertexOutput;
+
+    let world_from_local = get_world_from_local(input.instance_index);
+    let position = vec4<f32>(input.position, 1.0);
+
+    output.clip_position = mesh_position_local_to_clip(world_from_local, position);
+    output.world_position = mesh_position_local_to_world(world_from_local, position).xyz;
+    output.world_normal = mesh_normal_local_to_world(input.normal, input.instance_index);
+
+    return output;
+}
+
+struct FragmentInput {
+    @location(0) world_position: vec3<f32>,
+    @location(1) world_normal: vec3<f32>,
+}
+
+@group(2) @binding(0) var<uniform> color: vec3<f32>;
+// Position in xyz and intensity in w, of the first `light_count` lights
+@group(2) @binding(1) var<uniform> lights: array<vec4<f32>, MAX_LIGHTS>;
+@group(2) @binding(2) var<uniform> light_count: u32;
+// Number of flat shades between unlit and fully lit
+@group(2) @binding(3) var<uniform> bands: u32;
+// Fraction of the silhouette, seen edge-on, drawn as outline
+@group(2) @binding(4) var<uniform> outline_width: f32;
+
+const OUTLINE_COLOR: vec3<f32> = vec3<f32>(0.0, 0.0, 0.0);
+// Keeps the unlit side from going completely black
+const AMBIENT: f32 = 0.15;
+
+@fragment
+fn fragment(input: FragmentInput) -> @location(0) vec4<f32> {
+    let normal = normalize(input.world_normal);
+    let view_dir = normalize(view.world_position - input.world_position);
+
+    // Surfaces seen edge-on form the silhouette
+    if (dot(normal, view_dir) < outline_width) {
+        return vec4(OUTLINE_COLOR, 1.0);
+    }
+
+    // Light the white surface, then snap the brightness to the nearest band below
+    var lit = 0.0;
+    for (var i = 0u; i < min(light_count, MAX_LIGHTS); i++) {
+        lit += diffuse(input.world_position, normal, vec3(1.0), lights[i].xyz, lights[i].w).x;
+    }
+    let steps = f32(max(bands, 1u));
+    let shade = AMBIENT + (1.0 - AMBIENT) * min(floor(lit * steps) / steps, 1.0);
+
+    return vec4(color * shade, 1.0);
+}
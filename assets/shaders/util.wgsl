@@ -1,6 +1,9 @@
 // this is made available to the importing module
 // const COLOR_MULTIPLIER: vec4<f32> = vec4<f32>(1.0, 1.0, 1.0, 0.5);
 
+// Length of the light arrays, keep in step with `MAX_LIGHTS` in src/demo/materials.rs
+const MAX_LIGHTS: u32 = 4u;
+
 fn diffuse(
     pos: vec3<f32>,
     normal: vec3<f32>,
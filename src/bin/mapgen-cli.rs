@@ -0,0 +1,298 @@
+//! Generates a map without opening a window and writes it to disk.
+//!
+//! ```text
+//! mapgen-cli [--seed N] [--grid-size N] [--jitter F] [--threshold F] [--output PATH]
+//!            [--heightmap PATH] [--biomes PATH] [--resolution N] [--geojson PATH]
+//!            [--svg PATH] [--svg-outlines] [--glb PATH] [--terrain-height F]
+//!            [--save PATH] [--load PATH]
+//! ```
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    process::ExitCode,
+};
+
+use renderer::mapgen::{
+    export::{coastlines, write_color_map, write_geojson, write_glb, write_heightmap, write_svg},
+    generate_map, map_bounds, read_document, write_document, BiomePalette, GeneratedMap,
+    MapDocument, MapgenSettings, TerrainMesh,
+};
+use renderer::state::MapFormat;
+use serde_json::json;
+
+const USAGE: &str = "\
+Usage: mapgen-cli [OPTIONS]
+
+At least one of --output, --heightmap, --biomes, --geojson, --svg, --glb or --save is required.
+
+Options:
+  --seed <N>          RNG seed [default: 3735928559]
+  --grid-size <N>     Number of points along each side of the map [default: 20]
+  --jitter <F>        Random offset applied to each grid point, in cells, at most 0.5
+                      [default: 0.5]
+  --threshold <F>     Elevation below which regions are water [default: 0.65]
+  --output <PATH>     JSON file the generated map is written to
+  --heightmap <PATH>  16-bit grayscale PNG heightmap
+  --biomes <PATH>     PNG with every region filled with its biome color
+  --resolution <N>    Width and height of the PNG and SVG images [default: 1024]
+  --geojson <PATH>    GeoJSON with every region as a polygon, plus coastlines
+  --svg <PATH>        SVG with every region filled with its biome color
+  --svg-outlines      Stroke cell borders in the SVG
+  --glb <PATH>        Binary glTF of the map as a 3D terrain mesh
+  --terrain-height <F>  Height of the highest peak in the glTF, in map units [default: 2.0]
+  --save <PATH>       Map document that --load restores exactly, JSON if PATH ends in .json
+                      and binary otherwise
+  --load <PATH>       Restore a saved map document instead of generating one; the generation
+                      options above are ignored
+  -h, --help          Print this message";
+
+struct Args {
+    settings: MapgenSettings,
+    output: Option<String>,
+    heightmap: Option<String>,
+    biomes: Option<String>,
+    resolution: u32,
+    geojson: Option<String>,
+    svg: Option<String>,
+    svg_outlines: bool,
+    glb: Option<String>,
+    terrain_height: f32,
+    save: Option<String>,
+    load: Option<String>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut settings = MapgenSettings::default();
+    let mut output = None;
+    let mut heightmap = None;
+    let mut biomes = None;
+    let mut resolution = 1024;
+    let mut geojson = None;
+    let mut svg = None;
+    let mut svg_outlines = false;
+    let mut glb = None;
+    let mut terrain_height = 2.0;
+    let mut save = None;
+    let mut load = None;
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for {arg}"));
+
+        match arg.as_str() {
+            "--seed" => settings.rng_seed = parse(&arg, value()?)?,
+            "--grid-size" => settings.grid_size = parse(&arg, value()?)?,
+            "--jitter" => settings.jitter = parse(&arg, value()?)?,
+            "--threshold" => settings.elevation_threshold = parse(&arg, value()?)?,
+            "--output" | "-o" => output = Some(value()?),
+            "--heightmap" => heightmap = Some(value()?),
+            "--biomes" => biomes = Some(value()?),
+            "--resolution" => resolution = parse(&arg, value()?)?,
+            "--geojson" => geojson = Some(value()?),
+            "--svg" => svg = Some(value()?),
+            "--svg-outlines" => svg_outlines = true,
+            "--glb" => glb = Some(value()?),
+            "--terrain-height" => terrain_height = parse(&arg, value()?)?,
+            "--save" => save = Some(value()?),
+            "--load" => load = Some(value()?),
+            _ => return Err(format!("unexpected argument {arg}")),
+        }
+    }
+
+    let outputs = [&output, &heightmap, &biomes, &geojson, &svg, &glb, &save];
+    if outputs.iter().all(|path| path.is_none()) {
+        return Err("nothing to write, pass at least one output path".to_string());
+    }
+
+    if settings.grid_size < 2 {
+        return Err("--grid-size must be at least 2".to_string());
+    }
+
+    let threshold = settings.elevation_threshold;
+    if !(threshold > 0.0 && threshold < 1.0) {
+        return Err(format!(
+            "--threshold must be between 0 and 1, exclusive, got {threshold}"
+        ));
+    }
+
+    Ok(Args {
+        settings,
+        output,
+        heightmap,
+        biomes,
+        resolution,
+        geojson,
+        svg,
+        svg_outlines,
+        glb,
+        terrain_height,
+        save,
+        load,
+    })
+}
+
+fn parse<T: std::str::FromStr>(arg: &str, value: String) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value {value:?} for {arg}"))
+}
+
+fn write_json(map: &GeneratedMap, settings: &MapgenSettings, path: &str) -> std::io::Result<()> {
+    let document = json!({
+        "seed": settings.rng_seed,
+        "grid_size": settings.grid_size,
+        "elevation_threshold": settings.elevation_threshold,
+        "points": map.regions.points.iter().map(|p| [p.x, p.y]).collect::<Vec<_>>(),
+        "cells": map
+            .regions
+            .cells
+            .iter()
+            .map(|cell| cell.iter().map(|p| [p.x, p.y]).collect::<Vec<_>>())
+            .collect::<Vec<_>>(),
+        "neighbours": map.regions.neighbours,
+        "elevation": map.elevation,
+        "moisture": map.moisture,
+        "biomes": map.biomes.iter().map(|b| format!("{b:?}")).collect::<Vec<_>>(),
+    });
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer(&mut writer, &document)?;
+    writer.flush()
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+
+    let args = match parse_args(args.into_iter()) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("error: {err}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let (map, settings) = match &args.load {
+        Some(path) => {
+            let restored = read_document(path).and_then(|document| {
+                let settings = document.settings.clone();
+                document.restore().map(|map| (map, settings))
+            });
+
+            match restored {
+                Ok(restored) => restored,
+                Err(err) => {
+                    eprintln!("error: could not load {path}: {err}");
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+        None => match generate_map(&args.settings) {
+            Ok(map) => (map, args.settings.clone()),
+            Err(err) => {
+                eprintln!("error: could not generate map: {err}");
+                return ExitCode::FAILURE;
+            }
+        },
+    };
+
+    for (stage, duration) in &map.timings.stages {
+        eprintln!("{stage}: {:.2} ms", duration.as_secs_f64() * 1000.0);
+    }
+
+    let bounds = map_bounds(settings.grid_size);
+    let palette = BiomePalette::default();
+    let colors: Vec<_> = map.biomes.iter().map(|&b| palette.color(b)).collect();
+
+    let mut results = Vec::new();
+
+    if let Some(path) = &args.output {
+        results.push((path, write_json(&map, &settings, path)));
+    }
+
+    if let Some(path) = &args.heightmap {
+        let result = write_heightmap(
+            path,
+            &map.regions,
+            &map.elevation,
+            &bounds,
+            args.resolution,
+            args.resolution,
+        );
+        results.push((path, result));
+    }
+
+    if let Some(path) = &args.biomes {
+        let result = write_color_map(
+            path,
+            &map.regions,
+            &colors,
+            &bounds,
+            args.resolution,
+            args.resolution,
+        );
+        results.push((path, result));
+    }
+
+    if let Some(path) = &args.geojson {
+        let result = write_geojson(
+            path,
+            &map.regions,
+            &map.elevation,
+            &map.moisture,
+            &map.biomes,
+            settings.elevation_threshold,
+        );
+        results.push((path, result));
+    }
+
+    if let Some(path) = &args.svg {
+        let coastlines = coastlines(&map.regions, &map.elevation, settings.elevation_threshold);
+
+        let result = write_svg(
+            path,
+            &map.regions,
+            &colors,
+            &coastlines,
+            &bounds,
+            args.resolution,
+            args.svg_outlines,
+        );
+        results.push((path, result));
+    }
+
+    if let Some(path) = &args.glb {
+        let terrain = TerrainMesh::new(
+            &map.mesh,
+            &map.elevation,
+            &colors,
+            settings.elevation_threshold,
+            args.terrain_height,
+        );
+        results.push((path, write_glb(path, &terrain)));
+    }
+
+    if let Some(path) = &args.save {
+        let format = if path.ends_with(".json") {
+            MapFormat::Json
+        } else {
+            MapFormat::Binary
+        };
+        let document = MapDocument::new(&settings, &map.regions, &map.elevation);
+        results.push((path, write_document(path, &document, format)));
+    }
+
+    for (path, result) in results {
+        if let Err(err) = result {
+            eprintln!("error: could not write {path}: {err}");
+            return ExitCode::FAILURE;
+        }
+
+        eprintln!("wrote {path}");
+    }
+
+    ExitCode::SUCCESS
+}
@@ -9,7 +9,7 @@ use bevy::{
     math::{EulerRot, Quat, Vec2, Vec3},
     prelude::{any_with_component, Commands, DetectChanges, EventReader, IntoSystemConfigs, KeyCode, Query, Res, Transform},
 };
-use misc::{CameraAction, CameraBundle, CameraSettings, CameraState};
+pub use misc::{CameraAction, CameraBundle, CameraSettings, CameraState};
 
 use std::f32::consts::{FRAC_PI_2, PI, TAU};
 
@@ -0,0 +1,188 @@
+use bevy::{pbr::NotShadowCaster, prelude::*, window::PrimaryWindow};
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+use renderer::camera::CameraState;
+
+use super::{materials::LightMarkerMaterial, RendererDemo, MAX_LIGHTS};
+use crate::ui::LightSettings;
+
+/// Lumens of a point light per unit of `Light::intensity`.
+const LUMENS_PER_INTENSITY: f32 = 100_000.0;
+
+const MARKER_RADIUS: f32 = 0.05;
+
+/// The cursor picks a marker within this many radii of its center, small markers being hard
+/// to hit exactly.
+const PICK_SLACK: f32 = 2.0;
+
+const MARKER_COLOR: LinearRgba = LinearRgba::rgb(1.0, 0.87, 0.13);
+const DRAGGED_MARKER_COLOR: LinearRgba = LinearRgba::WHITE;
+
+/// A light of the demo and its marker, lighting the PBR material. The custom materials are lit
+/// from `LightSettings` directly.
+#[derive(Component)]
+pub struct DemoLight(pub usize);
+
+/// Light being dragged, with the offset from where it was grabbed to its center.
+#[derive(Resource, Default)]
+pub(super) struct LightDrag(Option<(usize, Vec3)>);
+
+#[derive(Resource)]
+pub(super) struct LightMarkers {
+    mesh: Handle<Mesh>,
+    material: Handle<LightMarkerMaterial>,
+    dragged: Handle<LightMarkerMaterial>,
+}
+
+impl FromWorld for LightMarkers {
+    fn from_world(world: &mut World) -> Self {
+        LightMarkers {
+            mesh: world.add_asset(Sphere::new(MARKER_RADIUS)),
+            material: world.add_asset(LightMarkerMaterial {
+                color: MARKER_COLOR,
+            }),
+            dragged: world.add_asset(LightMarkerMaterial {
+                color: DRAGGED_MARKER_COLOR,
+            }),
+        }
+    }
+}
+
+/// Positions and intensities packed for the custom materials.
+pub(super) fn packed_lights(light_settings: &LightSettings) -> ([Vec4; MAX_LIGHTS], u32) {
+    let mut lights = [Vec4::ZERO; MAX_LIGHTS];
+
+    for (packed, light) in lights.iter_mut().zip(&light_settings.lights) {
+        *packed = Vec3::from_array(light.pos).extend(light.intensity);
+    }
+
+    (lights, light_settings.lights.len().min(MAX_LIGHTS) as u32)
+}
+
+/// Spawns, moves and despawns the lights to match `LightSettings`.
+pub(super) fn sync_lights(
+    mut commands: Commands,
+    light_settings: Res<LightSettings>,
+    drag: Res<LightDrag>,
+    markers: Res<LightMarkers>,
+    mut lights: Query<(Entity, &DemoLight, &mut PointLight, &mut Transform)>,
+) {
+    let settings = &light_settings.lights[..light_settings.lights.len().min(MAX_LIGHTS)];
+    let count = lights.iter().len();
+
+    // Lights are despawned with the rest of the demo, so they may need spawning again
+    if !light_settings.is_changed() && !drag.is_changed() && count == settings.len() {
+        return;
+    }
+
+    let material = |i| {
+        let dragged = drag.0.is_some_and(|(dragged, _)| dragged == i);
+        MeshMaterial3d(if dragged {
+            markers.dragged.clone()
+        } else {
+            markers.material.clone()
+        })
+    };
+
+    // Lights are always spawned in order, so indices past the end are the ones removed
+    for (entity, light, mut point_light, mut transform) in lights.iter_mut() {
+        match settings.get(light.0) {
+            Some(settings) => {
+                point_light.intensity = settings.intensity * LUMENS_PER_INTENSITY;
+                transform.translation = Vec3::from_array(settings.pos);
+                commands.entity(entity).insert(material(light.0));
+            }
+            None => commands.entity(entity).despawn(),
+        }
+    }
+
+    for (i, settings) in settings.iter().enumerate().skip(count) {
+        commands.spawn((
+            RendererDemo,
+            DemoLight(i),
+            PointLight {
+                intensity: settings.intensity * LUMENS_PER_INTENSITY,
+                shadows_enabled: true,
+                ..default()
+            },
+            Transform::from_translation(Vec3::from_array(settings.pos)),
+            Mesh3d(markers.mesh.clone()),
+            material(i),
+            // The light sits inside its marker
+            NotShadowCaster,
+        ));
+    }
+}
+
+/// Drags a light marker with the left mouse button, in the plane facing the camera.
+pub(super) fn drag_lights(
+    buttons: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<CameraState>>,
+    mut egui: EguiContexts,
+    mut drag: ResMut<LightDrag>,
+    mut light_settings: ResMut<LightSettings>,
+) {
+    if buttons.just_released(MouseButton::Left) && drag.0.is_some() {
+        drag.0 = None;
+    }
+
+    let Ok((camera, camera_transform)) = cameras.get_single() else {
+        return;
+    };
+    let Some(ray) = windows
+        .get_single()
+        .ok()
+        .and_then(Window::cursor_position)
+        .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor).ok())
+    else {
+        return;
+    };
+
+    let over_ui = egui
+        .try_ctx_mut()
+        .is_some_and(|ctx| ctx.wants_pointer_input() || ctx.is_pointer_over_area());
+
+    if buttons.just_pressed(MouseButton::Left) && !over_ui {
+        // The marker closest to the camera among those under the cursor
+        drag.0 = light_settings
+            .lights
+            .iter()
+            .take(MAX_LIGHTS)
+            .enumerate()
+            .filter_map(|(i, light)| {
+                let center = Vec3::from_array(light.pos);
+                let t = (center - ray.origin).dot(*ray.direction);
+                let grabbed = ray.get_point(t);
+
+                (t > 0.0 && grabbed.distance(center) <= PICK_SLACK * MARKER_RADIUS).then_some((
+                    i,
+                    t,
+                    center - grabbed,
+                ))
+            })
+            .min_by(|(_, a, _), (_, b, _)| a.total_cmp(b))
+            .map(|(i, _, offset)| (i, offset));
+    }
+
+    let Some((i, offset)) = drag.0 else {
+        return;
+    };
+    let Some(light) = light_settings.lights.get(i) else {
+        drag.0 = None;
+        return;
+    };
+
+    let center = Vec3::from_array(light.pos);
+    let plane = InfinitePlane3d {
+        normal: camera_transform.forward(),
+    };
+
+    if let Some(t) = ray.intersect_plane(center - offset, plane) {
+        let pos = (ray.get_point(t) + offset).to_array();
+
+        // Compared first so the settings are not marked as changed while holding still
+        if pos != light.pos {
+            light_settings.lights[i].pos = pos;
+        }
+    }
+}
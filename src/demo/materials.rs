@@ -0,0 +1,121 @@
+use bevy::{
+    pbr::{MaterialPipeline, MaterialPipelineKey},
+    prelude::*,
+    render::{
+        mesh::MeshVertexBufferLayoutRef,
+        render_resource::{
+            AsBindGroup, RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError,
+        },
+    },
+};
+
+const LAMBERT_SHADER_PATH: &str = "shaders/lambert_material.wgsl";
+const TOON_SHADER_PATH: &str = "shaders/toon_material.wgsl";
+const LIGHT_SHADER_PATH: &str = "shaders/light.wgsl";
+
+/// Most lights the custom materials are lit by, see `assets/shaders/util.wgsl`.
+pub const MAX_LIGHTS: usize = 4;
+
+/// Diffuse-only shading from point lights.
+#[derive(Asset, TypePath, AsBindGroup, Clone, Default)]
+pub struct LambertMaterial {
+    #[uniform(0)]
+    pub color: Vec3,
+    /// Position in xyz and intensity in w, of the first `light_count` lights
+    #[uniform(1)]
+    pub lights: [Vec4; MAX_LIGHTS],
+    #[uniform(2)]
+    pub light_count: u32,
+}
+
+/// Cel shading in flat bands, with the silhouette outlined.
+#[derive(Asset, TypePath, AsBindGroup, Clone)]
+pub struct ToonMaterial {
+    #[uniform(0)]
+    pub color: Vec3,
+    #[uniform(1)]
+    pub lights: [Vec4; MAX_LIGHTS],
+    #[uniform(2)]
+    pub light_count: u32,
+    #[uniform(3)]
+    pub bands: u32,
+    #[uniform(4)]
+    pub outline_width: f32,
+}
+
+impl Default for ToonMaterial {
+    fn default() -> Self {
+        ToonMaterial {
+            color: Vec3::ONE,
+            lights: [Vec4::ZERO; MAX_LIGHTS],
+            light_count: 0,
+            bands: 3,
+            outline_width: 0.25,
+        }
+    }
+}
+
+/// Flat color for the light markers.
+#[derive(Asset, TypePath, AsBindGroup, Clone)]
+pub struct LightMarkerMaterial {
+    #[uniform(0)]
+    pub color: LinearRgba,
+}
+
+impl Material for LambertMaterial {
+    fn vertex_shader() -> ShaderRef {
+        LAMBERT_SHADER_PATH.into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        LAMBERT_SHADER_PATH.into()
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayoutRef,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        position_and_normal(descriptor, layout)
+    }
+}
+
+impl Material for ToonMaterial {
+    fn vertex_shader() -> ShaderRef {
+        TOON_SHADER_PATH.into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        TOON_SHADER_PATH.into()
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayoutRef,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        position_and_normal(descriptor, layout)
+    }
+}
+
+/// Feeds the two attributes the shaders read, whatever else the mesh carries.
+fn position_and_normal(
+    descriptor: &mut RenderPipelineDescriptor,
+    layout: &MeshVertexBufferLayoutRef,
+) -> Result<(), SpecializedMeshPipelineError> {
+    let vertex_layout = layout.0.get_layout(&[
+        Mesh::ATTRIBUTE_POSITION.at_shader_location(0),
+        Mesh::ATTRIBUTE_NORMAL.at_shader_location(1),
+    ])?;
+    descriptor.vertex.buffers = vec![vertex_layout];
+
+    Ok(())
+}
+
+impl Material for LightMarkerMaterial {
+    fn fragment_shader() -> ShaderRef {
+        LIGHT_SHADER_PATH.into()
+    }
+}
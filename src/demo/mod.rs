@@ -0,0 +1,162 @@
+mod lights;
+mod materials;
+
+use bevy::prelude::*;
+use renderer::{camera::CameraState, state::DemoState};
+
+pub use materials::{LambertMaterial, LightMarkerMaterial, ToonMaterial, MAX_LIGHTS};
+
+use crate::ui::{LightSettings, MaterialSettings, RendererState};
+
+const DRAGON_PATH: &str = "meshes/dragon.glb";
+
+/// Entities that only exist while the renderer demo is shown.
+#[derive(Component)]
+pub struct RendererDemo;
+
+#[derive(Component)]
+pub struct Dragon;
+
+/// One material per `RendererState`, kept in step with the settings.
+#[derive(Resource)]
+struct DemoMaterials {
+    lambert: Handle<LambertMaterial>,
+    toon: Handle<ToonMaterial>,
+    pbr: Handle<StandardMaterial>,
+}
+
+impl FromWorld for DemoMaterials {
+    fn from_world(world: &mut World) -> Self {
+        DemoMaterials {
+            lambert: world.add_asset(LambertMaterial::default()),
+            toon: world.add_asset(ToonMaterial::default()),
+            pbr: world.add_asset(StandardMaterial::default()),
+        }
+    }
+}
+
+pub struct DemoPlugin;
+
+impl Plugin for DemoPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            MaterialPlugin::<LambertMaterial>::default(),
+            MaterialPlugin::<ToonMaterial>::default(),
+            MaterialPlugin::<LightMarkerMaterial>::default(),
+        ));
+        app.init_resource::<DemoMaterials>();
+        app.init_resource::<lights::LightMarkers>();
+        app.init_resource::<lights::LightDrag>();
+
+        app.add_systems(OnEnter(DemoState::Renderer), spawn_demo);
+        app.add_systems(OnExit(DemoState::Renderer), despawn_demo);
+
+        app.add_systems(
+            Update,
+            (
+                frame_dragon.run_if(state_changed::<DemoState>),
+                (lights::drag_lights, lights::sync_lights).chain(),
+                (update_materials, apply_material),
+            )
+                .run_if(in_state(DemoState::Renderer)),
+        );
+    }
+}
+
+fn spawn_demo(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let mesh = asset_server.load(
+        GltfAssetLabel::Primitive {
+            mesh: 0,
+            primitive: 0,
+        }
+        .from_asset(DRAGON_PATH),
+    );
+
+    // The node rotation of the glTF scene, which loading the primitive alone skips
+    commands.spawn((
+        RendererDemo,
+        Dragon,
+        Mesh3d(mesh),
+        Transform::from_rotation(Quat::from_rotation_x(90f32.to_radians())),
+    ));
+}
+
+fn despawn_demo(mut commands: Commands, query: Query<Entity, With<RendererDemo>>) {
+    query
+        .iter()
+        .for_each(|entity| commands.entity(entity).despawn_recursive());
+}
+
+/// Points the pan-orbit camera at the dragon, which is about one unit tall.
+///
+/// Not done on entering the state, since the initial transition runs before the camera is
+/// spawned.
+fn frame_dragon(mut cameras: Query<&mut CameraState>) {
+    for mut camera in cameras.iter_mut() {
+        camera.center = Vec3::ZERO;
+        camera.radius = 3.0;
+        camera.pitch = -20f32.to_radians();
+        camera.yaw = 30f32.to_radians();
+    }
+}
+
+fn update_materials(
+    material_settings: Res<MaterialSettings>,
+    light: Res<LightSettings>,
+    handles: Res<DemoMaterials>,
+    mut lambert: ResMut<Assets<LambertMaterial>>,
+    mut toon: ResMut<Assets<ToonMaterial>>,
+    mut pbr: ResMut<Assets<StandardMaterial>>,
+) {
+    if !material_settings.is_changed() && !light.is_changed() {
+        return;
+    }
+
+    let color = Vec3::from_array(material_settings.color);
+    let (lights, light_count) = lights::packed_lights(&light);
+
+    if let Some(material) = lambert.get_mut(&handles.lambert) {
+        material.color = color;
+        material.lights = lights;
+        material.light_count = light_count;
+    }
+
+    if let Some(material) = toon.get_mut(&handles.toon) {
+        material.color = color;
+        material.lights = lights;
+        material.light_count = light_count;
+        material.bands = material_settings.toon_bands;
+        material.outline_width = material_settings.outline_width;
+    }
+
+    if let Some(material) = pbr.get_mut(&handles.pbr) {
+        material.base_color = Color::srgb_from_array(material_settings.color);
+    }
+}
+
+/// Gives the dragon the material of the current renderer state.
+fn apply_material(
+    mut commands: Commands,
+    renderer_state: Res<State<RendererState>>,
+    dragons: Query<(Entity, Ref<Dragon>)>,
+    handles: Res<DemoMaterials>,
+) {
+    for (entity, dragon) in dragons.iter() {
+        if !renderer_state.is_changed() && !dragon.is_added() {
+            continue;
+        }
+
+        let mut dragon = commands.entity(entity);
+        dragon.remove::<(
+            MeshMaterial3d<LambertMaterial>,
+            MeshMaterial3d<ToonMaterial>,
+            MeshMaterial3d<StandardMaterial>,
+        )>();
+
+        match renderer_state.get() {
+            RendererState::Basic => dragon.insert(MeshMaterial3d(handles.lambert.clone())),
+            RendererState::Toon => dragon.insert(MeshMaterial3d(handles.toon.clone())),
+            RendererState::Pbr => dragon.insert(MeshMaterial3d(handles.pbr.clone())),
+        };
+    }
+}
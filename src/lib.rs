@@ -0,0 +1,3 @@
+pub mod camera;
+pub mod mapgen;
+pub mod state;
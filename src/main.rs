@@ -1,22 +1,26 @@
-mod mapgen;
-mod state;
-
 use bevy::{prelude::*, window::PrimaryWindow};
 
 use bevy_inspector_egui::{
     bevy_egui::{EguiContext, EguiPlugin},
     egui,
 };
-use mapgen::{MapgenPlugin, MapgenSettings};
-use serde::{Deserialize, Serialize};
-use state::RegenCells;
-
-#[derive(Default, States, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum DemoState {
-    #[default]
-    Renderer,
-    Mapgen,
-}
+use demo::DemoPlugin;
+use renderer::{
+    camera::CameraPlugin,
+    mapgen::{
+        export::ExportSettings, BiomePalette, Biomes, BrushSettings, Elevation, GenerationTimings,
+        History, HistorySettings, HoveredCell, MapgenPlugin, MapgenSettings, Moisture,
+        PainterlySettings, Regions, TerrainSettings,
+    },
+    state::{
+        DemoState, ExportFormat, ExportMap, HistoryStep, LoadMap, MapFormat, MapView, RegenCells,
+        SaveMap,
+    },
+};
+use ui::UIPlugin;
+
+mod demo;
+mod ui;
 
 fn main() {
     App::new()
@@ -24,11 +28,13 @@ fn main() {
             DefaultPlugins,
             EguiPlugin,
             MapgenPlugin,
+            CameraPlugin,
             bevy_inspector_egui::DefaultInspectorConfigPlugin,
+            UIPlugin,
+            DemoPlugin,
         ))
-        .init_state::<DemoState>()
         .add_event::<RegenCells>()
-        .add_systems(Update, inspector_ui)
+        .add_systems(Update, inspector_ui.run_if(in_state(DemoState::Mapgen)))
         .run();
 }
 
@@ -50,8 +56,156 @@ fn inspector_ui(world: &mut World) {
                     world.send_event_default::<RegenCells>();
                 }
             });
+            bevy_inspector_egui::bevy_inspector::ui_for_resource::<BiomePalette>(world, ui);
+
+            ui.horizontal(|ui| {
+                bevy_inspector_egui::bevy_inspector::ui_for_resource::<TerrainSettings>(world, ui);
+
+                let view = *world.resource::<State<MapView>>().get();
+                for (label, next) in [
+                    ("2D", MapView::Flat),
+                    ("3D", MapView::Terrain),
+                    ("Painterly", MapView::Painterly),
+                ] {
+                    if ui.selectable_label(view == next, label).clicked() && view != next {
+                        world.resource_mut::<NextState<MapView>>().set(next);
+                    }
+                }
+            });
+            bevy_inspector_egui::bevy_inspector::ui_for_resource::<PainterlySettings>(world, ui);
+            bevy_inspector_egui::bevy_inspector::ui_for_resource::<BrushSettings>(world, ui);
+
+            ui.horizontal(|ui| {
+                bevy_inspector_egui::bevy_inspector::ui_for_resource::<HistorySettings>(world, ui);
+
+                let history = world.resource::<History>();
+                let (can_undo, can_redo) = (history.can_undo(), history.can_redo());
+
+                if ui
+                    .add_enabled(can_undo, egui::Button::new("Undo"))
+                    .clicked()
+                {
+                    world.send_event(HistoryStep::Undo);
+                }
+                if ui
+                    .add_enabled(can_redo, egui::Button::new("Redo"))
+                    .clicked()
+                {
+                    world.send_event(HistoryStep::Redo);
+                }
+            });
+
+            ui.horizontal(|ui| {
+                bevy_inspector_egui::bevy_inspector::ui_for_resource::<ExportSettings>(world, ui);
+                if ui.button("Export PNG").clicked() {
+                    world.send_event(ExportMap(ExportFormat::Png));
+                }
+                if ui.button("Export GeoJSON").clicked() {
+                    world.send_event(ExportMap(ExportFormat::GeoJson));
+                }
+                if ui.button("Export SVG").clicked() {
+                    world.send_event(ExportMap(ExportFormat::Svg));
+                }
+                if ui.button("Export GLB").clicked() {
+                    world.send_event(ExportMap(ExportFormat::Glb));
+                }
+            });
+
+            ui.horizontal(|ui| {
+                if ui.button("Save JSON").clicked() {
+                    world.send_event(SaveMap(MapFormat::Json));
+                }
+                if ui.button("Save Binary").clicked() {
+                    world.send_event(SaveMap(MapFormat::Binary));
+                }
+                if ui.button("Load JSON").clicked() {
+                    world.send_event(LoadMap(MapFormat::Json));
+                }
+                if ui.button("Load Binary").clicked() {
+                    world.send_event(LoadMap(MapFormat::Binary));
+                }
+            });
+
+            ui.collapsing("Hovered cell", |ui| cell_ui(world, ui));
+
+            let timings = world.resource::<GenerationTimings>();
+            ui.collapsing("Generation timings", |ui| {
+                for (stage, duration) in &timings.stages {
+                    ui.label(format!(
+                        "{stage}: {:.2} ms",
+                        duration.as_secs_f64() * 1000.0
+                    ));
+                }
+                ui.label(format!(
+                    "total: {:.2} ms",
+                    timings.total().as_secs_f64() * 1000.0
+                ));
+            });
 
             bevy_inspector_egui::bevy_inspector::ui_for_entities(world, ui);
         });
     });
 }
+
+/// Everything known about the cell under the cursor.
+fn cell_ui(world: &World, ui: &mut egui::Ui) {
+    let Some(r) = world.resource::<HoveredCell>().0 else {
+        ui.label("Hover a cell to inspect it");
+        return;
+    };
+
+    let regions = world.resource::<Regions>();
+    let (Some(point), Some(neighbours)) = (regions.points.get(r), regions.neighbours.get(r)) else {
+        return;
+    };
+    let elevation = world.resource::<Elevation>().0.get(r).copied();
+    let moisture = world.resource::<Moisture>().0.get(r).copied();
+    let biome = world.resource::<Biomes>().0.get(r).copied();
+    let threshold = world.resource::<MapgenSettings>().elevation_threshold;
+
+    egui::Grid::new("hovered_cell").show(ui, |ui| {
+        ui.label("Region");
+        ui.label(r.to_string());
+        ui.end_row();
+
+        ui.label("Position");
+        ui.label(format!("({:.2}, {:.2})", point.x, point.y));
+        ui.end_row();
+
+        if let Some(elevation) = elevation {
+            ui.label("Elevation");
+            ui.label(format!("{elevation:.3}"));
+            ui.end_row();
+
+            ui.label("Surface");
+            ui.label(if elevation < threshold {
+                "water"
+            } else {
+                "land"
+            });
+            ui.end_row();
+        }
+
+        if let Some(moisture) = moisture {
+            ui.label("Moisture");
+            ui.label(format!("{moisture:.3}"));
+            ui.end_row();
+        }
+
+        if let Some(biome) = biome {
+            ui.label("Biome");
+            ui.label(format!("{biome:?}"));
+            ui.end_row();
+        }
+
+        ui.label("Neighbours");
+        ui.label(
+            neighbours
+                .iter()
+                .map(|n| n.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+        );
+        ui.end_row();
+    });
+}
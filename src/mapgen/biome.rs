@@ -0,0 +1,139 @@
+use bevy::prelude::*;
+
+use super::utils::sea_relative;
+
+/// Whittaker-style biome of a region, keyed on elevation and moisture.
+#[derive(Reflect, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Biome {
+    Ocean,
+    Beach,
+    Desert,
+    Grassland,
+    Shrubland,
+    Forest,
+    RainForest,
+    Taiga,
+    Tundra,
+    Bare,
+    Snow,
+}
+
+impl Biome {
+    pub const ALL: [Biome; 11] = [
+        Biome::Ocean,
+        Biome::Beach,
+        Biome::Desert,
+        Biome::Grassland,
+        Biome::Shrubland,
+        Biome::Forest,
+        Biome::RainForest,
+        Biome::Taiga,
+        Biome::Tundra,
+        Biome::Bare,
+        Biome::Snow,
+    ];
+
+    /// Classifies a region from its sea-relative elevation (-1.0 to 1.0, sea level at 0.0) and
+    /// moisture (0.0 to 1.0).
+    pub fn classify(elevation: f64, moisture: f64) -> Biome {
+        let m = moisture.clamp(0.0, 1.0);
+
+        if elevation < 0.0 {
+            Biome::Ocean
+        } else if elevation < 0.05 {
+            Biome::Beach
+        } else if elevation > 0.8 {
+            match m {
+                m if m < 0.2 => Biome::Bare,
+                m if m < 0.5 => Biome::Tundra,
+                _ => Biome::Snow,
+            }
+        } else if elevation > 0.6 {
+            match m {
+                m if m < 0.33 => Biome::Desert,
+                m if m < 0.66 => Biome::Shrubland,
+                _ => Biome::Taiga,
+            }
+        } else if elevation > 0.3 {
+            match m {
+                m if m < 0.16 => Biome::Desert,
+                m if m < 0.5 => Biome::Grassland,
+                m if m < 0.83 => Biome::Forest,
+                _ => Biome::RainForest,
+            }
+        } else {
+            match m {
+                m if m < 0.16 => Biome::Desert,
+                m if m < 0.33 => Biome::Grassland,
+                m if m < 0.66 => Biome::Forest,
+                _ => Biome::RainForest,
+            }
+        }
+    }
+}
+
+/// Biome of each region.
+#[derive(Resource, Default)]
+pub struct Biomes(pub Vec<Biome>);
+
+pub fn assign_biomes(elevation: &[f64], moisture: &[f64], threshold: f64) -> Vec<Biome> {
+    elevation
+        .iter()
+        .zip(moisture)
+        .map(|(&e, &m)| Biome::classify(sea_relative(e, threshold), m))
+        .collect()
+}
+
+/// Colors used to paint each biome, editable from the inspector.
+#[derive(Resource, Reflect, Clone)]
+pub struct BiomePalette {
+    pub ocean: Color,
+    pub beach: Color,
+    pub desert: Color,
+    pub grassland: Color,
+    pub shrubland: Color,
+    pub forest: Color,
+    pub rain_forest: Color,
+    pub taiga: Color,
+    pub tundra: Color,
+    pub bare: Color,
+    pub snow: Color,
+    pub river: Color,
+}
+
+impl BiomePalette {
+    pub fn color(&self, biome: Biome) -> Color {
+        match biome {
+            Biome::Ocean => self.ocean,
+            Biome::Beach => self.beach,
+            Biome::Desert => self.desert,
+            Biome::Grassland => self.grassland,
+            Biome::Shrubland => self.shrubland,
+            Biome::Forest => self.forest,
+            Biome::RainForest => self.rain_forest,
+            Biome::Taiga => self.taiga,
+            Biome::Tundra => self.tundra,
+            Biome::Bare => self.bare,
+            Biome::Snow => self.snow,
+        }
+    }
+}
+
+impl Default for BiomePalette {
+    fn default() -> Self {
+        BiomePalette {
+            ocean: Color::hsl(240.0, 0.3, 0.5),
+            beach: Color::hsl(45.0, 0.45, 0.7),
+            desert: Color::hsl(40.0, 0.4, 0.65),
+            grassland: Color::hsl(90.0, 0.3, 0.5),
+            shrubland: Color::hsl(75.0, 0.2, 0.5),
+            forest: Color::hsl(110.0, 0.35, 0.35),
+            rain_forest: Color::hsl(130.0, 0.45, 0.28),
+            taiga: Color::hsl(150.0, 0.2, 0.4),
+            tundra: Color::hsl(60.0, 0.1, 0.6),
+            bare: Color::hsl(30.0, 0.05, 0.55),
+            snow: Color::hsl(0.0, 0.0, 0.95),
+            river: Color::hsl(220.0, 0.5, 0.4),
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use bevy::prelude::*;
+
+use super::{
+    map_bounds,
+    moisture::assign_moisture,
+    pointer::MapPointer,
+    render::river_mesh,
+    rivers::assign_rivers,
+    utils::{from_sea_relative, sea_relative},
+    DualMesh, Elevation, GenerationSettings, Moisture, Regions, RiverMesh,
+};
+
+/// What a brush stroke does to the elevation under it.
+#[derive(Reflect, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BrushTool {
+    /// Push the terrain up
+    Raise,
+    /// Push the terrain down
+    Lower,
+    /// Level the terrain to the height where the stroke started
+    Flatten,
+    /// Sink the terrain into deep water
+    Ocean,
+    /// Pile the terrain up into peaks
+    Mountain,
+}
+
+/// Elevation painting with the left mouse button, as in Mapgen4.
+#[derive(Resource, Reflect)]
+pub struct BrushSettings {
+    /// Paint while the left mouse button is held over the map
+    pub enabled: bool,
+    pub tool: BrushTool,
+    /// Radius of the brush, in map units
+    pub size: f64,
+    /// Change per second at the center of the brush, in sea-relative elevation
+    pub strength: f64,
+}
+
+impl Default for BrushSettings {
+    fn default() -> Self {
+        BrushSettings {
+            enabled: true,
+            tool: BrushTool::Raise,
+            size: 2.0,
+            strength: 1.0,
+        }
+    }
+}
+
+/// Sea-relative elevation [`BrushTool::Ocean`] pulls towards.
+const OCEAN_DEPTH: f64 = -0.5;
+
+/// Sea-relative elevation [`BrushTool::Mountain`] pulls towards.
+const MOUNTAIN_HEIGHT: f64 = 1.0;
+
+/// The stroke in progress, if any.
+#[derive(Resource, Default)]
+pub(super) struct BrushStroke {
+    /// Sea-relative elevation under the cursor when the stroke started
+    flatten_to: Option<f64>,
+    /// Elevation changed since moisture and rivers were last updated
+    painted: bool,
+    /// Region nearest to the brush last frame, where the search for the next one starts
+    nearest: usize,
+}
+
+impl BrushStroke {
+    pub fn in_progress(&self) -> bool {
+        self.flatten_to.is_some()
+    }
+
+    /// Has moisture and rivers recomputed as if a stroke had just ended.
+    pub fn touch(&mut self) {
+        self.painted = true;
+    }
+}
+
+/// Applies the brush to every region under the cursor.
+///
+/// Only elevation changes while painting, so biomes follow the brush every frame. Moisture and
+/// rivers are recomputed by [`finish_stroke`] once the button is released.
+pub(super) fn paint_elevation(
+    time: Res<Time>,
+    mut pointer: MapPointer,
+    brush: Res<BrushSettings>,
+    generation_settings: Res<GenerationSettings>,
+    regions: Res<Regions>,
+    mut elevation: ResMut<Elevation>,
+    mut stroke: ResMut<BrushStroke>,
+) {
+    if !brush.enabled || !pointer.pressed(MouseButton::Left) {
+        stroke.flatten_to = None;
+        return;
+    }
+    let Some(cursor) = pointer.position() else {
+        return;
+    };
+
+    let threshold = generation_settings.0.elevation_threshold;
+    let (x, y) = (cursor.x as f64, cursor.y as f64);
+    let distance = |r: usize| f64::hypot(regions.points[r].x - x, regions.points[r].y - y);
+
+    // Regions are only searched from points on the map. No site is farther from the nearest
+    // point on the map than from the cursor, so the brush can't reach past that search.
+    let bounds = map_bounds(generation_settings.0.grid_size);
+    let (center_x, center_y) = (
+        x.clamp(bounds.left(), bounds.right()),
+        y.clamp(bounds.top(), bounds.bottom()),
+    );
+
+    let Some(nearest) = regions.nearest(center_x, center_y, stroke.nearest) else {
+        return;
+    };
+    stroke.nearest = nearest;
+
+    let flatten_to = *stroke.flatten_to.get_or_insert_with(|| {
+        elevation
+            .0
+            .get(nearest)
+            .map_or(0.0, |&e| sea_relative(e, threshold))
+    });
+
+    let step = brush.strength * time.delta_secs_f64();
+    let mut changes = Vec::new();
+
+    for r in regions.within(center_x, center_y, brush.size, nearest) {
+        let d = distance(r) / brush.size;
+        let Some(&e) = elevation.0.get(r).filter(|_| d < 1.0) else {
+            continue;
+        };
+
+        // Smooth falloff, full strength at the center and none at the rim
+        let amount = step * (1.0 - d * d).powi(2);
+        let s = sea_relative(e, threshold);
+
+        let s = match brush.tool {
+            BrushTool::Raise => s + amount,
+            BrushTool::Lower => s - amount,
+            BrushTool::Flatten => s + (flatten_to - s) * amount.min(1.0),
+            BrushTool::Ocean => s + (OCEAN_DEPTH - s) * amount.min(1.0),
+            BrushTool::Mountain => s + (MOUNTAIN_HEIGHT - s) * amount.min(1.0),
+        };
+
+        let painted = from_sea_relative(s.clamp(-1.0, 1.0), threshold);
+        if painted != e {
+            changes.push((r, painted));
+        }
+    }
+
+    // Biomes and the history react to Elevation changing, so leave it alone unless a region
+    // actually moved, e.g. not while flattening ground that is already level
+    if changes.is_empty() {
+        return;
+    }
+
+    let elevation = &mut elevation.0;
+    for (r, e) in changes {
+        elevation[r] = e;
+    }
+    stroke.painted = true;
+}
+
+pub(super) fn stroke_finished(
+    stroke: Res<BrushStroke>,
+    buttons: Res<ButtonInput<MouseButton>>,
+) -> bool {
+    stroke.painted && !buttons.pressed(MouseButton::Left)
+}
+
+/// Brings moisture and rivers up to date with the painted elevation.
+pub(super) fn finish_stroke(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    river_meshes: Query<&Mesh2d, With<RiverMesh>>,
+    mesh: Res<DualMesh>,
+    elevation: Res<Elevation>,
+    generation_settings: Res<GenerationSettings>,
+    mut stroke: ResMut<BrushStroke>,
+) {
+    stroke.painted = false;
+
+    let settings = &generation_settings.0;
+    let threshold = settings.elevation_threshold;
+    let moisture = assign_moisture(&mesh, &elevation.0, threshold, &settings.moisture);
+    let rivers = assign_rivers(&mesh, &elevation.0, &moisture, threshold, &settings.rivers);
+
+    for handle in river_meshes.iter() {
+        meshes.insert(&handle.0, river_mesh(&rivers, settings.rivers.width));
+    }
+
+    commands.insert_resource(Moisture(moisture));
+    commands.insert_resource(rivers);
+}
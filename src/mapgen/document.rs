@@ -0,0 +1,237 @@
+use std::{fs, io, path::Path};
+
+use bevy::prelude::*;
+use delaunator::{Point, EPSILON};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{
+    export::ExportSettings, restore_map, Elevation, GeneratedMap, GenerationSettings, MapSpawner,
+    MapgenSettings, Regions,
+};
+use crate::state::{LoadMap, MapFormat, SaveMap};
+
+/// Version written by this build. Bump it whenever [`MapDocument`] changes shape, and upgrade
+/// documents with the old layout in [`MapDocument::from_json`] and [`MapDocument::from_bytes`].
+pub const MAP_VERSION: u32 = 1;
+
+/// Leading bytes of a binary map document.
+const MAGIC: &[u8; 4] = b"MAPG";
+
+/// Everything needed to bring a map back exactly as it was saved.
+///
+/// Only the inputs of the pipeline are stored; moisture, rivers and biomes are derived again
+/// on load, which is cheap compared to sampling and relaxing the points.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MapDocument {
+    /// Format version, kept as the first field so it can be read before the rest
+    pub version: u32,
+    /// Settings the map was generated with, including the seed
+    pub settings: MapgenSettings,
+    /// Final site of each region, after relaxation
+    pub points: Vec<[f64; 2]>,
+    /// Elevation of each region, including any hand edits
+    pub elevation: Vec<f64>,
+}
+
+impl MapDocument {
+    pub fn new(settings: &MapgenSettings, regions: &Regions, elevation: &[f64]) -> Self {
+        MapDocument {
+            version: MAP_VERSION,
+            settings: settings.clone(),
+            points: regions.points.iter().map(|p| [p.x, p.y]).collect(),
+            elevation: elevation.to_vec(),
+        }
+    }
+
+    /// Rebuilds the map without sampling anything from the seed.
+    pub fn restore(self) -> io::Result<GeneratedMap> {
+        self.settings.validate().map_err(invalid)?;
+        check_points(&self.points)?;
+
+        let len = self.points.len();
+        if self.elevation.len() != len {
+            return Err(invalid(format!(
+                "map has {len} points but {} elevations",
+                self.elevation.len()
+            )));
+        }
+
+        let points = self.points.iter().map(|&[x, y]| Point { x, y }).collect();
+        let map = restore_map(&self.settings, points, self.elevation).ok_or_else(|| {
+            invalid(format!(
+                "the {len} points of the map do not form a Voronoi diagram"
+            ))
+        })?;
+
+        // The Voronoi diagram drops sites outside the map bounds
+        if map.regions.len() != len {
+            return Err(invalid(format!(
+                "only {} of {len} points lie inside a map of size {}",
+                map.regions.len(),
+                self.settings.grid_size
+            )));
+        }
+
+        Ok(map)
+    }
+
+    pub fn to_json(&self) -> io::Result<Vec<u8>> {
+        Ok(serde_json::to_vec(self)?)
+    }
+
+    pub fn from_json(bytes: &[u8]) -> io::Result<Self> {
+        let document: Value = serde_json::from_slice(bytes)?;
+        let version = document
+            .get("version")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| invalid("map document has no version".to_string()))?;
+
+        match version {
+            1 => Ok(serde_json::from_value(document)?),
+            _ => Err(unsupported(version)),
+        }
+    }
+
+    /// Encodes the document as [`MAGIC`] followed by a bincode payload.
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        let mut bytes = MAGIC.to_vec();
+        bincode::serialize_into(&mut bytes, self).map_err(invalid)?;
+
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        let payload = bytes
+            .strip_prefix(MAGIC)
+            .ok_or_else(|| invalid("not a binary map document".to_string()))?;
+
+        // bincode writes the leading `version` field as a little-endian u32
+        let version = payload
+            .first_chunk::<4>()
+            .map(|version| u32::from_le_bytes(*version))
+            .ok_or_else(|| invalid("map document has no version".to_string()))?;
+
+        match version {
+            1 => bincode::deserialize(payload).map_err(invalid),
+            _ => Err(unsupported(version.into())),
+        }
+    }
+}
+
+pub fn write_document(
+    path: impl AsRef<Path>,
+    document: &MapDocument,
+    format: MapFormat,
+) -> io::Result<()> {
+    let bytes = match format {
+        MapFormat::Json => document.to_json()?,
+        MapFormat::Binary => document.to_bytes()?,
+    };
+
+    fs::write(path, bytes)
+}
+
+/// Reads a map document in either format, telling them apart by the binary header.
+pub fn read_document(path: impl AsRef<Path>) -> io::Result<MapDocument> {
+    let bytes = fs::read(path)?;
+
+    if bytes.starts_with(MAGIC) {
+        MapDocument::from_bytes(&bytes)
+    } else {
+        MapDocument::from_json(&bytes)
+    }
+}
+
+/// Rejects the points the Voronoi diagram can't be built from. Sites that coincide, within the
+/// tolerance Delaunay triangulation uses, make the cell builder panic.
+fn check_points(points: &[[f64; 2]]) -> io::Result<()> {
+    if let Some([x, y]) = points
+        .iter()
+        .find(|[x, y]| !x.is_finite() || !y.is_finite())
+    {
+        return Err(invalid(format!("map has a point at ({x}, {y})")));
+    }
+
+    let mut sorted = points.to_vec();
+    sorted.sort_unstable_by(|a, b| a[0].total_cmp(&b[0]));
+
+    for (i, [x, y]) in sorted.iter().enumerate() {
+        let duplicate = sorted[i + 1..]
+            .iter()
+            .take_while(|other| other[0] - x <= EPSILON)
+            .any(|other| (other[1] - y).abs() <= EPSILON);
+
+        if duplicate {
+            return Err(invalid(format!("map has two points at ({x}, {y})")));
+        }
+    }
+
+    Ok(())
+}
+
+fn file_name(format: MapFormat) -> &'static str {
+    match format {
+        MapFormat::Json => "map.json",
+        MapFormat::Binary => "map.bin",
+    }
+}
+
+fn invalid<E>(err: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+fn unsupported(version: u64) -> io::Error {
+    invalid(format!(
+        "unsupported map version {version}, this build reads up to version {MAP_VERSION}"
+    ))
+}
+
+pub(super) fn save_map(
+    mut events: EventReader<SaveMap>,
+    export_settings: Res<ExportSettings>,
+    generation_settings: Res<GenerationSettings>,
+    regions: Res<Regions>,
+    elevation: Res<Elevation>,
+) {
+    for SaveMap(format) in events.read() {
+        let path = Path::new(&export_settings.directory).join(file_name(*format));
+        let document = MapDocument::new(&generation_settings.0, &regions, &elevation.0);
+
+        match write_document(&path, &document, *format) {
+            Ok(_) => info!("Saved {}", path.display()),
+            Err(err) => error!("Could not save map: {err}"),
+        }
+    }
+}
+
+pub(super) fn load_map(
+    mut events: EventReader<LoadMap>,
+    mut spawner: MapSpawner,
+    export_settings: Res<ExportSettings>,
+) {
+    for LoadMap(format) in events.read() {
+        let path = Path::new(&export_settings.directory).join(file_name(*format));
+
+        let document = match read_document(&path) {
+            Ok(document) => document,
+            Err(err) => {
+                error!("Could not load {}: {err}", path.display());
+                continue;
+            }
+        };
+
+        let settings = document.settings.clone();
+        match document.restore() {
+            Ok(map) => {
+                spawner.spawn(&settings, map);
+                spawner.commands.insert_resource(settings);
+                info!("Loaded {}", path.display());
+            }
+            Err(err) => error!("Could not load {}: {err}", path.display()),
+        }
+    }
+}
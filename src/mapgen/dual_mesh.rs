@@ -0,0 +1,281 @@
+use bevy::prelude::Resource;
+use delaunator::{next_halfedge, prev_halfedge, triangulate, Point, EMPTY};
+
+/// Delaunay triangulation of the region sites together with its Voronoi dual, laid out like
+/// Mapgen4's `DualMesh`.
+///
+/// Regions (`r`) are the Delaunay vertices / Voronoi cells, triangles (`t`) are the Delaunay
+/// triangles / Voronoi vertices and sides (`s`) are directed half-edges. Side `s` belongs to
+/// triangle `s / 3`, starts at region `s_begin_r(s)` and is paired with `s_opposite_s(s)`.
+///
+/// The hull is closed with a single ghost region and a fan of ghost triangles, so every side has
+/// an opposite and walking around any region never runs off the edge of the map. Ghost elements
+/// are numbered after all solid ones.
+#[derive(Resource, Default, Clone)]
+pub struct DualMesh {
+    num_solid_sides: usize,
+    num_solid_regions: usize,
+    r_vertex: Vec<Point>,
+    t_vertex: Vec<Point>,
+    s_begin_r: Vec<usize>,
+    s_opposite_s: Vec<usize>,
+    r_in_s: Vec<usize>,
+    r_boundary: Vec<bool>,
+}
+
+impl DualMesh {
+    pub fn new(points: &[Point]) -> Self {
+        let triangulation = triangulate(points);
+        let num_solid_sides = triangulation.triangles.len();
+        let num_solid_regions = points.len();
+        let ghost_r = num_solid_regions;
+
+        let mut s_begin_r = triangulation.triangles;
+        let mut s_opposite_s = triangulation.halfedges;
+
+        // Close the hull: pair each unpaired side with a ghost triangle connecting it to the
+        // ghost region, walking the hull in order so consecutive ghost triangles are adjacent.
+        let mut r_unpaired_s = vec![EMPTY; num_solid_regions];
+        let mut first_unpaired_s = EMPTY;
+        let mut num_unpaired_sides = 0;
+        for s in 0..num_solid_sides {
+            if s_opposite_s[s] == EMPTY {
+                r_unpaired_s[s_begin_r[s]] = s;
+                first_unpaired_s = s;
+                num_unpaired_sides += 1;
+            }
+        }
+
+        s_begin_r.resize(num_solid_sides + 3 * num_unpaired_sides, ghost_r);
+        s_opposite_s.resize(num_solid_sides + 3 * num_unpaired_sides, EMPTY);
+
+        let mut s = first_unpaired_s;
+        for i in 0..num_unpaired_sides {
+            let ghost_s = num_solid_sides + 3 * i;
+            s_opposite_s[s] = ghost_s;
+            s_opposite_s[ghost_s] = s;
+            s_begin_r[ghost_s] = s_begin_r[next_halfedge(s)];
+            s_begin_r[ghost_s + 1] = s_begin_r[s];
+            s_begin_r[ghost_s + 2] = ghost_r;
+
+            let k = num_solid_sides + (3 * i + 4) % (3 * num_unpaired_sides);
+            s_opposite_s[ghost_s + 2] = k;
+            s_opposite_s[k] = ghost_s + 2;
+
+            s = r_unpaired_s[s_begin_r[next_halfedge(s)]];
+        }
+
+        let mut r_vertex = points.to_vec();
+        r_vertex.push(centroid(points));
+
+        let num_regions = r_vertex.len();
+        let mut r_in_s = vec![EMPTY; num_regions];
+        let mut r_boundary = vec![false; num_regions];
+        for s in 0..s_begin_r.len() {
+            let endpoint = s_begin_r[next_halfedge(s)];
+            if r_in_s[endpoint] == EMPTY {
+                r_in_s[endpoint] = s;
+            }
+            if s_begin_r[s] == ghost_r {
+                r_boundary[endpoint] = true;
+            }
+        }
+        r_boundary[ghost_r] = false;
+
+        let mut mesh = DualMesh {
+            num_solid_sides,
+            num_solid_regions,
+            r_vertex,
+            t_vertex: Vec::new(),
+            s_begin_r,
+            s_opposite_s,
+            r_in_s,
+            r_boundary,
+        };
+
+        mesh.t_vertex = (0..mesh.num_triangles())
+            .map(|t| {
+                if mesh.is_ghost_t(t) {
+                    // Ghost triangles sit just outside the middle of their hull side
+                    let s = 3 * t;
+                    let a = mesh.r_pos(mesh.s_begin_r(s));
+                    let b = mesh.r_pos(mesh.s_end_r(s));
+                    let (dx, dy) = (b.x - a.x, b.y - a.y);
+                    let scale = 0.5 / f64::hypot(dx, dy).max(f64::EPSILON);
+
+                    Point {
+                        x: 0.5 * (a.x + b.x) + dy * scale,
+                        y: 0.5 * (a.y + b.y) - dx * scale,
+                    }
+                } else {
+                    let [a, b, c] = mesh.t_circulate_r(t).map(|r| mesh.r_pos(r));
+                    circumcenter(a, b, c)
+                }
+            })
+            .collect();
+
+        mesh
+    }
+
+    pub fn num_regions(&self) -> usize {
+        self.r_vertex.len()
+    }
+
+    pub fn num_solid_regions(&self) -> usize {
+        self.num_solid_regions
+    }
+
+    pub fn num_sides(&self) -> usize {
+        self.s_begin_r.len()
+    }
+
+    pub fn num_solid_sides(&self) -> usize {
+        self.num_solid_sides
+    }
+
+    pub fn num_triangles(&self) -> usize {
+        self.s_begin_r.len() / 3
+    }
+
+    pub fn num_solid_triangles(&self) -> usize {
+        self.num_solid_sides / 3
+    }
+
+    /// Site of region `r`. The ghost region is placed at the centroid of all sites.
+    pub fn r_pos(&self, r: usize) -> &Point {
+        &self.r_vertex[r]
+    }
+
+    /// Voronoi vertex of triangle `t`: the circumcenter for solid triangles, a point just
+    /// outside the hull for ghost triangles.
+    pub fn t_pos(&self, t: usize) -> &Point {
+        &self.t_vertex[t]
+    }
+
+    pub fn s_begin_r(&self, s: usize) -> usize {
+        self.s_begin_r[s]
+    }
+
+    pub fn s_end_r(&self, s: usize) -> usize {
+        self.s_begin_r[next_halfedge(s)]
+    }
+
+    pub fn s_inner_t(&self, s: usize) -> usize {
+        s / 3
+    }
+
+    pub fn s_outer_t(&self, s: usize) -> usize {
+        self.s_opposite_s[s] / 3
+    }
+
+    pub fn s_opposite_s(&self, s: usize) -> usize {
+        self.s_opposite_s[s]
+    }
+
+    pub fn s_next_s(&self, s: usize) -> usize {
+        next_halfedge(s)
+    }
+
+    pub fn s_prev_s(&self, s: usize) -> usize {
+        prev_halfedge(s)
+    }
+
+    pub fn t_circulate_s(&self, t: usize) -> [usize; 3] {
+        [3 * t, 3 * t + 1, 3 * t + 2]
+    }
+
+    pub fn t_circulate_r(&self, t: usize) -> [usize; 3] {
+        self.t_circulate_s(t).map(|s| self.s_begin_r[s])
+    }
+
+    pub fn t_circulate_t(&self, t: usize) -> [usize; 3] {
+        self.t_circulate_s(t).map(|s| self.s_outer_t(s))
+    }
+
+    /// Sides ending at region `r`, in counter-clockwise order.
+    pub fn r_circulate_s(&self, r: usize) -> RegionSides<'_> {
+        let start = self.r_in_s[r];
+
+        RegionSides {
+            mesh: self,
+            start,
+            next: start,
+        }
+    }
+
+    /// Regions adjacent to region `r`, including the ghost region for boundary regions.
+    pub fn r_circulate_r(&self, r: usize) -> impl Iterator<Item = usize> + '_ {
+        self.r_circulate_s(r).map(|s| self.s_begin_r(s))
+    }
+
+    /// Triangles touching region `r`, i.e. the corners of its Voronoi cell.
+    pub fn r_circulate_t(&self, r: usize) -> impl Iterator<Item = usize> + '_ {
+        self.r_circulate_s(r).map(|s| self.s_inner_t(s))
+    }
+
+    pub fn is_ghost_r(&self, r: usize) -> bool {
+        r == self.num_solid_regions
+    }
+
+    pub fn is_ghost_s(&self, s: usize) -> bool {
+        s >= self.num_solid_sides
+    }
+
+    pub fn is_ghost_t(&self, t: usize) -> bool {
+        self.is_ghost_s(3 * t)
+    }
+
+    /// Whether solid region `r` lies on the convex hull of the sites.
+    pub fn is_boundary_r(&self, r: usize) -> bool {
+        self.r_boundary[r]
+    }
+}
+
+/// Iterator over the sides ending at a region, see [`DualMesh::r_circulate_s`].
+pub struct RegionSides<'a> {
+    mesh: &'a DualMesh,
+    start: usize,
+    next: usize,
+}
+
+impl Iterator for RegionSides<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.next == EMPTY {
+            return None;
+        }
+
+        let incoming = self.next;
+        let outgoing = next_halfedge(incoming);
+        self.next = self.mesh.s_opposite_s[outgoing];
+        if self.next == self.start {
+            self.next = EMPTY;
+        }
+
+        Some(incoming)
+    }
+}
+
+fn centroid(points: &[Point]) -> Point {
+    let n = points.len().max(1) as f64;
+    let (x, y) = points
+        .iter()
+        .fold((0.0, 0.0), |(x, y), p| (x + p.x, y + p.y));
+
+    Point { x: x / n, y: y / n }
+}
+
+fn circumcenter(a: &Point, b: &Point, c: &Point) -> Point {
+    let (bx, by) = (b.x - a.x, b.y - a.y);
+    let (cx, cy) = (c.x - a.x, c.y - a.y);
+
+    let bl = bx * bx + by * by;
+    let cl = cx * cx + cy * cy;
+    let d = 0.5 / (bx * cy - by * cx);
+
+    Point {
+        x: a.x + (cy * bl - by * cl) * d,
+        y: a.y + (bx * cl - cx * bl) * d,
+    }
+}
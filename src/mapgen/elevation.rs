@@ -0,0 +1,131 @@
+use bevy::prelude::Reflect;
+use noise::{
+    Billow, Fbm, MultiFractal, NoiseFn, OpenSimplex, Perlin, RidgedMulti, Seedable, Simplex,
+    SuperSimplex, Value,
+};
+use serde::{Deserialize, Serialize};
+use voronoice::{BoundingBox, Point};
+
+/// Base noise function sampled by every octave.
+#[derive(Reflect, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum NoiseType {
+    Simplex,
+    Perlin,
+    OpenSimplex,
+    SuperSimplex,
+    Value,
+}
+
+/// How the octaves of the base noise are combined.
+#[derive(Reflect, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FractalType {
+    /// Plain fractal Brownian motion, rolling hills
+    Fbm,
+    /// Inverted absolute value, sharp mountain ridges
+    Ridged,
+    /// Absolute value, rounded billowy lumps
+    Billow,
+}
+
+/// Mask pulling elevation down towards the edges of the map.
+#[derive(Reflect, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum IslandShape {
+    /// Falls off with the distance to the nearest edge
+    Square,
+    /// Falls off with the distance to the center
+    Radial,
+    /// No mask, land can run off the edges of the map
+    Continent,
+}
+
+#[derive(Reflect, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct ElevationSettings {
+    pub noise: NoiseType,
+    pub fractal: FractalType,
+    pub octaves: usize,
+    /// Frequency of the first octave, in features per map width
+    pub frequency: f64,
+    /// Frequency multiplier between octaves
+    pub lacunarity: f64,
+    /// Amplitude multiplier between octaves
+    pub persistence: f64,
+    pub island_shape: IslandShape,
+    /// How strongly the island mask lowers the edges of the map
+    pub falloff: f64,
+}
+
+impl Default for ElevationSettings {
+    fn default() -> Self {
+        ElevationSettings {
+            noise: NoiseType::Simplex,
+            fractal: FractalType::Fbm,
+            octaves: 1,
+            frequency: 2.0,
+            lacunarity: 2.0,
+            persistence: 0.5,
+            island_shape: IslandShape::Square,
+            falloff: 1.0,
+        }
+    }
+}
+
+/// Samples the elevation of every point.
+///
+/// Points are first normalized to `bounds`, running from -0.5 to 0.5 across the map, so the
+/// same seed and settings give the same island shape whatever the grid size.
+pub fn assign_elevation(
+    points: &[Point],
+    bounds: &BoundingBox,
+    seed: u32,
+    settings: &ElevationSettings,
+) -> Vec<f64> {
+    let noise = build_noise(seed, settings);
+    let center = bounds.center();
+
+    points
+        .iter()
+        .map(|point| {
+            let nx = (point.x - center.x) / bounds.width();
+            let ny = (point.y - center.y) / bounds.height();
+
+            let e = 1.0 + noise.get([nx, ny]) / 2.0;
+
+            let d = match settings.island_shape {
+                IslandShape::Square => 2.0 * f64::max(f64::abs(nx), f64::abs(ny)),
+                IslandShape::Radial => 2.0 * f64::hypot(nx, ny),
+                IslandShape::Continent => 0.5,
+            };
+
+            (1.0 + e - settings.falloff * d) / 2.0
+        })
+        .collect()
+}
+
+fn build_noise(seed: u32, settings: &ElevationSettings) -> Box<dyn NoiseFn<f64, 2>> {
+    match settings.noise {
+        NoiseType::Simplex => build_fractal::<Simplex>(seed, settings),
+        NoiseType::Perlin => build_fractal::<Perlin>(seed, settings),
+        NoiseType::OpenSimplex => build_fractal::<OpenSimplex>(seed, settings),
+        NoiseType::SuperSimplex => build_fractal::<SuperSimplex>(seed, settings),
+        NoiseType::Value => build_fractal::<Value>(seed, settings),
+    }
+}
+
+fn build_fractal<T>(seed: u32, settings: &ElevationSettings) -> Box<dyn NoiseFn<f64, 2>>
+where
+    T: Default + Seedable + NoiseFn<f64, 2> + 'static,
+{
+    match settings.fractal {
+        FractalType::Fbm => Box::new(configure(Fbm::<T>::new(seed), settings)),
+        FractalType::Ridged => Box::new(configure(RidgedMulti::<T>::new(seed), settings)),
+        FractalType::Billow => Box::new(configure(Billow::<T>::new(seed), settings)),
+    }
+}
+
+fn configure<F: MultiFractal>(fractal: F, settings: &ElevationSettings) -> F {
+    fractal
+        .set_octaves(settings.octaves)
+        .set_frequency(settings.frequency)
+        .set_lacunarity(settings.lacunarity)
+        .set_persistence(settings.persistence)
+}
@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+use voronoice::Point;
+
+use crate::mapgen::Regions;
+
+/// Coastlines of the map as polylines running along the cell edges between land and water.
+///
+/// Closed coastlines, such as those of islands, start and end on the same point.
+pub fn coastlines(regions: &Regions, elevation: &[f64], threshold: f64) -> Vec<Vec<Point>> {
+    let is_land = |r: usize| elevation[r] >= threshold;
+    let mut segments = Vec::new();
+
+    for (r, neighbours) in regions.neighbours.iter().enumerate() {
+        if !is_land(r) {
+            continue;
+        }
+
+        for &n in neighbours {
+            if is_land(n) {
+                continue;
+            }
+
+            if let Some(segment) = shared_edge(&regions.cells[r], &regions.cells[n]) {
+                segments.push(segment);
+            }
+        }
+    }
+
+    chain(segments)
+}
+
+/// The two vertices shared by adjacent cells. Voronoi vertices are shared exactly, so plain
+/// equality is enough.
+fn shared_edge(a: &[Point], b: &[Point]) -> Option<(Point, Point)> {
+    let mut shared = a.iter().filter(|p| b.contains(p));
+
+    match (shared.next(), shared.next()) {
+        (Some(p), Some(q)) => Some((p.clone(), q.clone())),
+        _ => None,
+    }
+}
+
+fn key(p: &Point) -> (u64, u64) {
+    (p.x.to_bits(), p.y.to_bits())
+}
+
+/// Joins segments sharing endpoints into the longest possible polylines.
+fn chain(segments: Vec<(Point, Point)>) -> Vec<Vec<Point>> {
+    let mut by_endpoint: HashMap<(u64, u64), Vec<usize>> = HashMap::new();
+    for (i, (a, b)) in segments.iter().enumerate() {
+        by_endpoint.entry(key(a)).or_default().push(i);
+        by_endpoint.entry(key(b)).or_default().push(i);
+    }
+
+    let mut used = vec![false; segments.len()];
+    let mut lines = Vec::new();
+
+    // Start open lines from their dangling ends first, then pick up the closed loops
+    let mut starts: Vec<usize> = (0..segments.len())
+        .filter(|&i| {
+            let (a, b) = &segments[i];
+            by_endpoint[&key(a)].len() == 1 || by_endpoint[&key(b)].len() == 1
+        })
+        .collect();
+    starts.extend(0..segments.len());
+
+    for start in starts {
+        if used[start] {
+            continue;
+        }
+        used[start] = true;
+
+        let (a, b) = &segments[start];
+        let (first, second) = if by_endpoint[&key(b)].len() == 1 {
+            (b.clone(), a.clone())
+        } else {
+            (a.clone(), b.clone())
+        };
+        let mut line = vec![first, second];
+
+        loop {
+            let end = key(line.last().unwrap());
+            let next = by_endpoint[&end].iter().copied().find(|&i| !used[i]);
+
+            let Some(next) = next else {
+                break;
+            };
+            used[next] = true;
+
+            let (a, b) = &segments[next];
+            line.push(if key(a) == end { b.clone() } else { a.clone() });
+        }
+
+        lines.push(line);
+    }
+
+    lines
+}
@@ -0,0 +1,92 @@
+use std::{fs::File, io, io::BufWriter, path::Path};
+
+use serde_json::{json, Value};
+use voronoice::Point;
+
+use super::coastline::coastlines;
+use crate::mapgen::{Biome, Regions};
+
+/// Every region as a GeoJSON Polygon feature, followed by the coastlines as LineString features.
+///
+/// Coordinates are in map units. Regions carry their index, elevation, moisture, biome and
+/// whether they are land as properties.
+pub fn geojson(
+    regions: &Regions,
+    elevation: &[f64],
+    moisture: &[f64],
+    biomes: &[Biome],
+    threshold: f64,
+) -> Value {
+    let mut features = Vec::with_capacity(regions.len());
+
+    for (r, cell) in regions.cells.iter().enumerate() {
+        if cell.len() < 3 {
+            continue;
+        }
+
+        features.push(json!({
+            "type": "Feature",
+            "geometry": {
+                "type": "Polygon",
+                "coordinates": [ring(cell)],
+            },
+            "properties": {
+                "kind": "region",
+                "region": r,
+                "elevation": elevation[r],
+                "moisture": moisture[r],
+                "biome": format!("{:?}", biomes[r]),
+                "land": elevation[r] >= threshold,
+            },
+        }));
+    }
+
+    for line in coastlines(regions, elevation, threshold) {
+        features.push(json!({
+            "type": "Feature",
+            "geometry": {
+                "type": "LineString",
+                "coordinates": line.iter().map(|p| [p.x, p.y]).collect::<Vec<_>>(),
+            },
+            "properties": {
+                "kind": "coastline",
+            },
+        }));
+    }
+
+    json!({
+        "type": "FeatureCollection",
+        "features": features,
+    })
+}
+
+pub fn write_geojson(
+    path: impl AsRef<Path>,
+    regions: &Regions,
+    elevation: &[f64],
+    moisture: &[f64],
+    biomes: &[Biome],
+    threshold: f64,
+) -> io::Result<()> {
+    let document = geojson(regions, elevation, moisture, biomes, threshold);
+    let writer = BufWriter::new(File::create(path)?);
+
+    Ok(serde_json::to_writer(writer, &document)?)
+}
+
+/// Closed, counter-clockwise linear ring as required by RFC 7946.
+fn ring(cell: &[Point]) -> Vec<[f64; 2]> {
+    let area: f64 = cell
+        .iter()
+        .zip(cell.iter().cycle().skip(1))
+        .map(|(a, b)| a.x * b.y - b.x * a.y)
+        .sum();
+
+    let mut ring: Vec<[f64; 2]> = cell.iter().map(|p| [p.x, p.y]).collect();
+    if area < 0.0 {
+        ring.reverse();
+    }
+    ring.push(ring[0]);
+
+    ring
+}
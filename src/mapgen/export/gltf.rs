@@ -0,0 +1,134 @@
+use std::{fs, io, path::Path};
+
+use serde_json::json;
+
+use crate::mapgen::TerrainMesh;
+
+const GLB_MAGIC: u32 = 0x4654_6C67;
+const GLB_VERSION: u32 = 2;
+const CHUNK_JSON: u32 = 0x4E4F_534A;
+const CHUNK_BIN: u32 = 0x004E_4942;
+
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+const TRIANGLES: u32 = 4;
+
+/// Encodes `terrain` as a binary glTF 2.0 file with a single vertex-colored mesh.
+pub fn glb(terrain: &TerrainMesh) -> Vec<u8> {
+    let mut bin = Vec::new();
+    let positions = push_floats(&mut bin, terrain.positions.as_flattened());
+    let normals = push_floats(&mut bin, terrain.normals.as_flattened());
+    let colors = push_floats(&mut bin, terrain.colors.as_flattened());
+    let indices = (bin.len(), terrain.indices.len() * 4);
+    bin.extend(terrain.indices.iter().flat_map(|i| i.to_le_bytes()));
+
+    let (min, max) = terrain.positions.iter().fold(
+        ([f32::INFINITY; 3], [f32::NEG_INFINITY; 3]),
+        |(mut min, mut max), p| {
+            for i in 0..3 {
+                min[i] = min[i].min(p[i]);
+                max[i] = max[i].max(p[i]);
+            }
+            (min, max)
+        },
+    );
+
+    let view = |(offset, length): (usize, usize), target: u32| {
+        json!({
+            "buffer": 0,
+            "byteOffset": offset,
+            "byteLength": length,
+            "target": target,
+        })
+    };
+
+    let document = json!({
+        "asset": { "version": "2.0", "generator": "mapgen" },
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [{ "mesh": 0, "name": "terrain" }],
+        "materials": [{
+            "name": "terrain",
+            "pbrMetallicRoughness": {
+                "baseColorFactor": [1.0, 1.0, 1.0, 1.0],
+                "metallicFactor": 0.0,
+                "roughnessFactor": 1.0,
+            },
+        }],
+        "meshes": [{
+            "name": "terrain",
+            "primitives": [{
+                "attributes": { "POSITION": 0, "NORMAL": 1, "COLOR_0": 2 },
+                "indices": 3,
+                "material": 0,
+                "mode": TRIANGLES,
+            }],
+        }],
+        "buffers": [{ "byteLength": bin.len() }],
+        "bufferViews": [
+            view(positions, ARRAY_BUFFER),
+            view(normals, ARRAY_BUFFER),
+            view(colors, ARRAY_BUFFER),
+            view(indices, ELEMENT_ARRAY_BUFFER),
+        ],
+        "accessors": [
+            {
+                "bufferView": 0,
+                "componentType": FLOAT,
+                "count": terrain.positions.len(),
+                "type": "VEC3",
+                "min": min,
+                "max": max,
+            },
+            {
+                "bufferView": 1,
+                "componentType": FLOAT,
+                "count": terrain.normals.len(),
+                "type": "VEC3",
+            },
+            {
+                "bufferView": 2,
+                "componentType": FLOAT,
+                "count": terrain.colors.len(),
+                "type": "VEC4",
+            },
+            {
+                "bufferView": 3,
+                "componentType": UNSIGNED_INT,
+                "count": terrain.indices.len(),
+                "type": "SCALAR",
+            },
+        ],
+    });
+
+    let mut json = serde_json::to_vec(&document).expect("glTF document should serialize");
+    json.resize(json.len().next_multiple_of(4), b' ');
+    bin.resize(bin.len().next_multiple_of(4), 0);
+
+    let length = 12 + 8 + json.len() + 8 + bin.len();
+    let mut glb = Vec::with_capacity(length);
+    for word in [GLB_MAGIC, GLB_VERSION, length as u32] {
+        glb.extend(word.to_le_bytes());
+    }
+    for (kind, chunk) in [(CHUNK_JSON, json), (CHUNK_BIN, bin)] {
+        glb.extend((chunk.len() as u32).to_le_bytes());
+        glb.extend(kind.to_le_bytes());
+        glb.extend(chunk);
+    }
+
+    glb
+}
+
+pub fn write_glb(path: impl AsRef<Path>, terrain: &TerrainMesh) -> io::Result<()> {
+    fs::write(path, glb(terrain))
+}
+
+/// Appends `values` to `bin` and returns the byte range they occupy.
+fn push_floats(bin: &mut Vec<u8>, values: &[f32]) -> (usize, usize) {
+    let offset = bin.len();
+    bin.extend(values.iter().flat_map(|v| v.to_le_bytes()));
+
+    (offset, bin.len() - offset)
+}
@@ -0,0 +1,140 @@
+use std::{fs::File, io, io::BufWriter, path::Path};
+
+use bevy::color::{Color, ColorToPacked, Srgba};
+use voronoice::BoundingBox;
+
+use crate::mapgen::{utils::cell_contains, Regions};
+
+/// Region covering each pixel of a `width` x `height` image of `bounds`, row by row from the
+/// top. Pixels outside every cell are `None`.
+pub fn rasterize(
+    regions: &Regions,
+    bounds: &BoundingBox,
+    width: u32,
+    height: u32,
+) -> Vec<Option<usize>> {
+    let (width, height) = (width as usize, height as usize);
+    let mut pixels = vec![None; width * height];
+
+    let center = bounds.center();
+    let left = center.x - bounds.width() / 2.0;
+    let top = center.y + bounds.height() / 2.0;
+    let pixel_width = bounds.width() / width as f64;
+    let pixel_height = bounds.height() / height as f64;
+
+    for (r, cell) in regions.cells.iter().enumerate() {
+        if cell.len() < 3 {
+            continue;
+        }
+
+        let (mut min_x, mut max_x) = (f64::INFINITY, f64::NEG_INFINITY);
+        let (mut min_y, mut max_y) = (f64::INFINITY, f64::NEG_INFINITY);
+        for p in cell {
+            min_x = min_x.min(p.x);
+            max_x = max_x.max(p.x);
+            min_y = min_y.min(p.y);
+            max_y = max_y.max(p.y);
+        }
+
+        let column_range = pixel_span(min_x - left, max_x - left, pixel_width, width);
+        let row_range = pixel_span(top - max_y, top - min_y, pixel_height, height);
+
+        for row in row_range {
+            let y = top - (row as f64 + 0.5) * pixel_height;
+            for column in column_range.clone() {
+                let x = left + (column as f64 + 0.5) * pixel_width;
+                if cell_contains(cell, x, y) {
+                    pixels[row * width + column] = Some(r);
+                }
+            }
+        }
+    }
+
+    pixels
+}
+
+/// Writes `elevation` as a 16-bit grayscale PNG, stretched so the lowest region is black and
+/// the highest white.
+pub fn write_heightmap(
+    path: impl AsRef<Path>,
+    regions: &Regions,
+    elevation: &[f64],
+    bounds: &BoundingBox,
+    width: u32,
+    height: u32,
+) -> io::Result<()> {
+    let min = elevation.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = elevation.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let range = (max - min).max(f64::EPSILON);
+
+    let data: Vec<u8> = rasterize(regions, bounds, width, height)
+        .into_iter()
+        .flat_map(|r| {
+            let e = r.map_or(0.0, |r| (elevation[r] - min) / range);
+            ((e * u16::MAX as f64).round() as u16).to_be_bytes()
+        })
+        .collect();
+
+    write_png(
+        path,
+        width,
+        height,
+        png::ColorType::Grayscale,
+        png::BitDepth::Sixteen,
+        &data,
+    )
+}
+
+/// Writes the map as an 8-bit RGB PNG with each region filled with its color.
+pub fn write_color_map(
+    path: impl AsRef<Path>,
+    regions: &Regions,
+    region_colors: &[Color],
+    bounds: &BoundingBox,
+    width: u32,
+    height: u32,
+) -> io::Result<()> {
+    let data: Vec<u8> = rasterize(regions, bounds, width, height)
+        .into_iter()
+        .flat_map(|r| {
+            let color = r.map_or(Srgba::BLACK, |r| region_colors[r].to_srgba());
+            let [red, green, blue, _] = color.to_u8_array();
+            [red, green, blue]
+        })
+        .collect();
+
+    write_png(
+        path,
+        width,
+        height,
+        png::ColorType::Rgb,
+        png::BitDepth::Eight,
+        &data,
+    )
+}
+
+fn write_png(
+    path: impl AsRef<Path>,
+    width: u32,
+    height: u32,
+    color: png::ColorType,
+    depth: png::BitDepth,
+    data: &[u8],
+) -> io::Result<()> {
+    let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), width, height);
+    encoder.set_color(color);
+    encoder.set_depth(depth);
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(data)?;
+
+    Ok(writer.finish()?)
+}
+
+/// Pixels whose centers may fall between `from` and `to`, measured from the image edge.
+fn pixel_span(from: f64, to: f64, pixel_size: f64, len: usize) -> std::ops::Range<usize> {
+    let start = (from / pixel_size - 0.5).floor().max(0.0) as usize;
+    let end = ((to / pixel_size - 0.5).ceil() + 1.0).clamp(0.0, len as f64) as usize;
+
+    start.min(end)..end
+}
@@ -0,0 +1,152 @@
+mod coastline;
+mod geojson;
+mod gltf;
+mod image;
+mod svg;
+
+use std::path::Path;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+pub use coastline::coastlines;
+pub use geojson::{geojson, write_geojson};
+pub use gltf::{glb, write_glb};
+pub use image::{rasterize, write_color_map, write_heightmap};
+pub use svg::{svg, write_svg};
+
+use super::{
+    map_bounds, BiomePalette, Biomes, DualMesh, Elevation, GenerationSettings, MapgenSettings,
+    Moisture, Regions, TerrainMesh,
+};
+use crate::state::{ExportFormat, ExportMap};
+
+#[derive(Resource, Reflect)]
+pub struct ExportSettings {
+    /// Directory exported files are written to
+    pub directory: String,
+    /// Width and height of exported images, in pixels
+    pub resolution: u32,
+    /// Stroke the border of every cell in SVG exports
+    pub svg_outlines: bool,
+    /// Height of the highest peak above sea level in glTF exports, in map units
+    pub terrain_height: f32,
+}
+
+impl Default for ExportSettings {
+    fn default() -> Self {
+        ExportSettings {
+            directory: ".".to_string(),
+            resolution: 1024,
+            svg_outlines: false,
+            terrain_height: 2.0,
+        }
+    }
+}
+
+/// The current map as stored in the world.
+#[derive(SystemParam)]
+pub(super) struct MapData<'w> {
+    regions: Res<'w, Regions>,
+    mesh: Res<'w, DualMesh>,
+    elevation: Res<'w, Elevation>,
+    moisture: Res<'w, Moisture>,
+    biomes: Res<'w, Biomes>,
+}
+
+pub(super) fn export_map(
+    mut events: EventReader<ExportMap>,
+    export_settings: Res<ExportSettings>,
+    mapgen_settings: Res<MapgenSettings>,
+    generation_settings: Res<GenerationSettings>,
+    palette: Res<BiomePalette>,
+    map: MapData,
+) {
+    let directory = Path::new(&export_settings.directory);
+    // The live settings may have a new grid size the current map wasn't generated with
+    let bounds = map_bounds(generation_settings.0.grid_size);
+    let resolution = export_settings.resolution;
+    let (regions, elevation) = (&*map.regions, &map.elevation.0);
+
+    let colors: Vec<Color> = map.biomes.0.iter().map(|&b| palette.color(b)).collect();
+
+    for event in events.read() {
+        match event.0 {
+            ExportFormat::Png => {
+                let heightmap = directory.join("heightmap.png");
+                let biome_map = directory.join("biomes.png");
+
+                let result = write_heightmap(
+                    &heightmap, regions, elevation, &bounds, resolution, resolution,
+                )
+                .and_then(|_| {
+                    write_color_map(
+                        &biome_map, regions, &colors, &bounds, resolution, resolution,
+                    )
+                });
+
+                match result {
+                    Ok(_) => info!(
+                        "Exported {} and {}",
+                        heightmap.display(),
+                        biome_map.display()
+                    ),
+                    Err(err) => error!("Could not export PNG images: {err}"),
+                }
+            }
+            ExportFormat::GeoJson => {
+                let path = directory.join("map.geojson");
+
+                let result = write_geojson(
+                    &path,
+                    regions,
+                    elevation,
+                    &map.moisture.0,
+                    &map.biomes.0,
+                    mapgen_settings.elevation_threshold,
+                );
+
+                match result {
+                    Ok(_) => info!("Exported {}", path.display()),
+                    Err(err) => error!("Could not export GeoJSON: {err}"),
+                }
+            }
+            ExportFormat::Svg => {
+                let path = directory.join("map.svg");
+
+                let coastlines =
+                    coastlines(regions, elevation, mapgen_settings.elevation_threshold);
+
+                let result = write_svg(
+                    &path,
+                    regions,
+                    &colors,
+                    &coastlines,
+                    &bounds,
+                    resolution,
+                    export_settings.svg_outlines,
+                );
+
+                match result {
+                    Ok(_) => info!("Exported {}", path.display()),
+                    Err(err) => error!("Could not export SVG: {err}"),
+                }
+            }
+            ExportFormat::Glb => {
+                let path = directory.join("terrain.glb");
+
+                let terrain = TerrainMesh::new(
+                    &map.mesh,
+                    elevation,
+                    &colors,
+                    mapgen_settings.elevation_threshold,
+                    export_settings.terrain_height,
+                );
+
+                match write_glb(&path, &terrain) {
+                    Ok(_) => info!("Exported {}", path.display()),
+                    Err(err) => error!("Could not export glTF: {err}"),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use std::{fmt::Write as _, fs, io, path::Path};
+
+use bevy::color::Color;
+use voronoice::{BoundingBox, Point};
+
+use crate::mapgen::Regions;
+
+const OUTLINE_WIDTH: f64 = 0.02;
+const COASTLINE_WIDTH: f64 = 0.08;
+
+/// Renders the map as an SVG document `size` pixels wide, one filled path per cell.
+///
+/// The view box is in map units with y flipped to point down, so the image matches what the
+/// app shows. `coastlines` are always stroked, cell borders only when `outlines` is set.
+pub fn svg(
+    regions: &Regions,
+    region_colors: &[Color],
+    coastlines: &[Vec<Point>],
+    bounds: &BoundingBox,
+    size: u32,
+    outlines: bool,
+) -> String {
+    let center = bounds.center();
+    let (width, height) = (bounds.width(), bounds.height());
+    let left = center.x - width / 2.0;
+    let top = center.y + height / 2.0;
+    let to_svg = |p: &Point| (p.x - left, top - p.y);
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{size}" height="{}" viewBox="0 0 {width} {height}">"#,
+        (size as f64 * height / width).round(),
+    );
+
+    let stroke = if outlines {
+        format!(r##" stroke="#000000" stroke-opacity="0.3" stroke-width="{OUTLINE_WIDTH}""##)
+    } else {
+        String::new()
+    };
+    let _ = writeln!(svg, r#"<g id="cells" stroke-linejoin="round"{stroke}>"#);
+
+    for (r, cell) in regions.cells.iter().enumerate() {
+        if cell.len() < 3 {
+            continue;
+        }
+
+        let mut d = String::new();
+        for (i, p) in cell.iter().enumerate() {
+            let (x, y) = to_svg(p);
+            let _ = write!(d, "{}{x:.4},{y:.4}", if i == 0 { "M" } else { "L" });
+        }
+        d.push('Z');
+
+        let fill = region_colors[r].to_srgba().to_hex();
+        let _ = writeln!(svg, r#"<path d="{d}" fill="{fill}"/>"#);
+    }
+    svg.push_str("</g>\n");
+
+    let _ = writeln!(
+        svg,
+        r##"<g id="coastline" fill="none" stroke="#202020" stroke-width="{COASTLINE_WIDTH}" stroke-linecap="round" stroke-linejoin="round">"##
+    );
+    for line in coastlines {
+        let points: Vec<String> = line
+            .iter()
+            .map(|p| {
+                let (x, y) = to_svg(p);
+                format!("{x:.4},{y:.4}")
+            })
+            .collect();
+
+        let _ = writeln!(svg, r#"<polyline points="{}"/>"#, points.join(" "));
+    }
+    svg.push_str("</g>\n</svg>\n");
+
+    svg
+}
+
+pub fn write_svg(
+    path: impl AsRef<Path>,
+    regions: &Regions,
+    region_colors: &[Color],
+    coastlines: &[Vec<Point>],
+    bounds: &BoundingBox,
+    size: u32,
+    outlines: bool,
+) -> io::Result<()> {
+    fs::write(
+        path,
+        svg(regions, region_colors, coastlines, bounds, size, outlines),
+    )
+}
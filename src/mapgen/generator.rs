@@ -0,0 +1,271 @@
+use std::{
+    collections::HashSet,
+    time::{Duration, Instant},
+};
+
+use bevy::prelude::Resource;
+use delaunator::Point;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use voronoice::{BoundingBox, VoronoiBuilder};
+
+use super::{
+    biome::{assign_biomes, Biome},
+    dual_mesh::DualMesh,
+    elevation::assign_elevation,
+    moisture::assign_moisture,
+    points::generate_points,
+    rivers::{assign_rivers, Rivers},
+    MapgenSettings,
+};
+
+/// Geometry of every region of a generated map, indexed by region.
+#[derive(Resource, Default, Clone)]
+pub struct Regions {
+    /// Voronoi site of each region
+    pub points: Vec<Point>,
+    /// Vertices of each region's Voronoi cell, clipped to the map bounds
+    pub cells: Vec<Vec<Point>>,
+    /// Regions sharing an edge with each region
+    pub neighbours: Vec<Vec<usize>>,
+}
+
+impl Regions {
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Region whose site is nearest to `(x, y)`, found by walking the neighbours from region
+    /// `start` towards the point.
+    ///
+    /// The walk only visits the regions between `start` and the point, so passing the answer
+    /// for a nearby point keeps it short. Exact for points inside the map bounds: a site that
+    /// isn't the nearest always has a neighbour across the cell edge facing the point.
+    pub fn nearest(&self, x: f64, y: f64, start: usize) -> Option<usize> {
+        let distance = |r: usize| f64::hypot(self.points[r].x - x, self.points[r].y - y);
+
+        let mut nearest = start.min(self.len().checked_sub(1)?);
+        loop {
+            let closer = self.neighbours[nearest]
+                .iter()
+                .copied()
+                .min_by(|&a, &b| distance(a).total_cmp(&distance(b)))
+                .filter(|&r| distance(r) < distance(nearest));
+
+            match closer {
+                Some(r) => nearest = r,
+                None => return Some(nearest),
+            }
+        }
+    }
+
+    /// Regions whose site lies within `radius` of `(x, y)`, a point inside the map bounds.
+    ///
+    /// Spreads out from the nearest site, see [`Regions::nearest`], so only the regions inside
+    /// the circle and along its rim are visited.
+    pub fn within(&self, x: f64, y: f64, radius: f64, start: usize) -> Vec<usize> {
+        let inside = |r: usize| f64::hypot(self.points[r].x - x, self.points[r].y - y) < radius;
+
+        let Some(nearest) = self.nearest(x, y, start).filter(|&r| inside(r)) else {
+            return Vec::new();
+        };
+
+        let mut found = vec![nearest];
+        let mut seen = HashSet::from([nearest]);
+        let mut next = 0;
+
+        while let Some(&r) = found.get(next) {
+            next += 1;
+
+            for &n in &self.neighbours[r] {
+                if inside(n) && seen.insert(n) {
+                    found.push(n);
+                }
+            }
+        }
+
+        found
+    }
+}
+
+/// Everything produced by a single run of the generator.
+pub struct GeneratedMap {
+    pub regions: Regions,
+    pub mesh: DualMesh,
+    pub elevation: Vec<f64>,
+    pub moisture: Vec<f64>,
+    pub biomes: Vec<Biome>,
+    pub rivers: Rivers,
+    pub timings: GenerationTimings,
+}
+
+/// Area covered by a map generated with the given `grid_size`; Voronoi cells are clipped to it
+/// and elevation is sampled relative to it.
+pub fn map_bounds(grid_size: usize) -> BoundingBox {
+    let center = Point {
+        x: (grid_size / 2) as f64,
+        y: (grid_size / 2) as f64,
+    };
+
+    BoundingBox::new(center, grid_size as f64, grid_size as f64)
+}
+
+/// Wall-clock time spent in each stage of the last generation, in pipeline order.
+#[derive(Resource, Default, Clone)]
+pub struct GenerationTimings {
+    pub stages: Vec<(&'static str, Duration)>,
+}
+
+impl GenerationTimings {
+    pub fn total(&self) -> Duration {
+        self.stages.iter().map(|(_, duration)| *duration).sum()
+    }
+
+    fn time<T>(&mut self, stage: &'static str, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = f();
+        self.stages.push((stage, start.elapsed()));
+
+        result
+    }
+}
+
+/// Runs the whole generation pipeline for `settings` without touching the ECS.
+///
+/// The output only depends on `settings`, so the same settings always yield the same map.
+/// Fails when the settings are out of range, see [`MapgenSettings::validate`].
+pub fn generate_map(settings: &MapgenSettings) -> Result<GeneratedMap, String> {
+    settings.validate()?;
+
+    let mut timings = GenerationTimings::default();
+    let mut rng = ChaCha8Rng::seed_from_u64(settings.rng_seed);
+
+    let bounds = map_bounds(settings.grid_size);
+    let points = timings.time("points", || {
+        generate_points(
+            settings.point_distribution,
+            settings.grid_size,
+            settings.jitter,
+            &bounds,
+            &mut rng,
+        )
+    });
+
+    let lloyd_iterations = if settings.relax_points {
+        settings.lloyd_iterations
+    } else {
+        0
+    };
+
+    let regions = build_regions(points, settings.grid_size, lloyd_iterations, &mut timings)
+        .ok_or("the points do not form a Voronoi diagram")?;
+
+    // Sampled at the final sites so elevation lines up with the relaxed cells
+    let elevation = timings.time("elevation", || {
+        assign_elevation(
+            &regions.points,
+            &bounds,
+            settings.rng_seed as u32,
+            &settings.elevation,
+        )
+    });
+
+    Ok(finish_map(settings, regions, elevation, timings))
+}
+
+/// Rebuilds a map from its final sites and elevation, as stored in a
+/// [`MapDocument`](super::MapDocument).
+///
+/// Nothing is sampled from the seed, so hand-edited elevation comes back as it was saved.
+/// Returns `None` when the points don't form a Voronoi diagram, e.g. fewer than three of them
+/// lie inside the map or all of them are collinear.
+pub fn restore_map(
+    settings: &MapgenSettings,
+    points: Vec<Point>,
+    elevation: Vec<f64>,
+) -> Option<GeneratedMap> {
+    let mut timings = GenerationTimings::default();
+
+    // The sites were already relaxed before they were saved
+    let regions = build_regions(points, settings.grid_size, 0, &mut timings)?;
+
+    Some(finish_map(settings, regions, elevation, timings))
+}
+
+fn build_regions(
+    points: Vec<Point>,
+    grid_size: usize,
+    lloyd_iterations: usize,
+    timings: &mut GenerationTimings,
+) -> Option<Regions> {
+    let voronoi = timings.time("voronoi", || {
+        VoronoiBuilder::default()
+            .set_sites(points)
+            .set_bounding_box(map_bounds(grid_size))
+            .set_lloyd_relaxation_iterations(lloyd_iterations)
+            .build()
+    })?;
+
+    let regions = timings.time("regions", || {
+        let mut regions = Regions {
+            points: voronoi.sites().clone(),
+            cells: Vec::with_capacity(voronoi.sites().len()),
+            neighbours: Vec::with_capacity(voronoi.sites().len()),
+        };
+
+        for cell in voronoi.iter_cells() {
+            regions.cells.push(cell.iter_vertices().cloned().collect());
+            regions.neighbours.push(cell.iter_neighbors().collect());
+        }
+
+        regions
+    });
+
+    Some(regions)
+}
+
+/// Derives everything that follows from the regions and their elevation.
+fn finish_map(
+    settings: &MapgenSettings,
+    regions: Regions,
+    elevation: Vec<f64>,
+    mut timings: GenerationTimings,
+) -> GeneratedMap {
+    let mesh = timings.time("dual mesh", || DualMesh::new(&regions.points));
+
+    let moisture = timings.time("moisture", || {
+        assign_moisture(
+            &mesh,
+            &elevation,
+            settings.elevation_threshold,
+            &settings.moisture,
+        )
+    });
+    let rivers = timings.time("rivers", || {
+        assign_rivers(
+            &mesh,
+            &elevation,
+            &moisture,
+            settings.elevation_threshold,
+            &settings.rivers,
+        )
+    });
+
+    let biomes = timings.time("biomes", || {
+        assign_biomes(&elevation, &moisture, settings.elevation_threshold)
+    });
+
+    GeneratedMap {
+        regions,
+        mesh,
+        elevation,
+        moisture,
+        biomes,
+        rivers,
+        timings,
+    }
+}
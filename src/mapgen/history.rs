@@ -0,0 +1,312 @@
+use std::{collections::VecDeque, mem::size_of};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+
+use super::{
+    brush::BrushStroke, Elevation, GenerationSettings, MapDocument, MapSpawner, MapgenSettings,
+    Regions,
+};
+use crate::state::HistoryStep;
+
+#[derive(Resource, Reflect)]
+pub struct HistorySettings {
+    /// Most edits that can be undone
+    pub depth: usize,
+    /// Memory the undo stack may use before the oldest edits are dropped, in megabytes
+    pub memory_limit_mb: usize,
+}
+
+impl Default for HistorySettings {
+    fn default() -> Self {
+        HistorySettings {
+            depth: 100,
+            memory_limit_mb: 64,
+        }
+    }
+}
+
+/// Settings changes less than this many seconds apart are undone together, so dragging a
+/// value in the inspector is a single edit.
+const MERGE_WINDOW: f64 = 0.5;
+
+/// A single undoable change, holding enough to apply it in both directions.
+enum Edit {
+    Settings {
+        before: Box<MapgenSettings>,
+        after: Box<MapgenSettings>,
+    },
+    /// Elevation of the regions touched by a brush stroke
+    Elevation(Vec<(usize, f64, f64)>),
+    /// The whole map was regenerated or loaded
+    Map {
+        before: Box<MapDocument>,
+        after: Box<MapDocument>,
+    },
+}
+
+impl Edit {
+    /// Rough number of bytes kept alive by the edit.
+    fn size(&self) -> usize {
+        let document = |document: &MapDocument| {
+            size_of::<MapDocument>()
+                + document.points.len() * size_of::<[f64; 2]>()
+                + document.elevation.len() * size_of::<f64>()
+        };
+
+        size_of::<Edit>()
+            + match self {
+                Edit::Settings { .. } => 2 * size_of::<MapgenSettings>(),
+                Edit::Elevation(changes) => changes.len() * size_of::<(usize, f64, f64)>(),
+                Edit::Map { before, after } => document(before) + document(after),
+            }
+    }
+}
+
+/// Undo and redo stacks, plus the state the last edit left the map in.
+///
+/// Edits are not announced by the systems making them. [`record_edits`] instead compares the
+/// map against `committed` and records whatever differs, so the inspector, the brush,
+/// regeneration and loading are all covered.
+#[derive(Resource, Default)]
+pub struct History {
+    undo: VecDeque<Edit>,
+    redo: Vec<Edit>,
+    /// The map, with the settings it was generated with
+    committed: Option<MapDocument>,
+    /// The inspector settings, which may have changed since the map was generated
+    settings: MapgenSettings,
+    /// Elevation changed since it was last compared against `committed`
+    elevation_dirty: bool,
+    /// When the settings last changed, in seconds since startup
+    last_settings_change: f64,
+}
+
+impl History {
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    fn push(&mut self, edit: Edit, settings: &HistorySettings) {
+        self.redo.clear();
+        self.undo.push_back(edit);
+
+        let limit = settings.memory_limit_mb * 1024 * 1024;
+        let mut size: usize = self.undo.iter().map(Edit::size).sum();
+
+        while self.undo.len() > settings.depth || (size > limit && !self.undo.is_empty()) {
+            if let Some(edit) = self.undo.pop_front() {
+                size -= edit.size();
+            }
+        }
+    }
+}
+
+pub(super) fn history_shortcuts(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut egui: EguiContexts,
+    mut events: EventWriter<HistoryStep>,
+) {
+    // Text fields in the inspector have their own undo
+    if egui
+        .try_ctx_mut()
+        .is_some_and(|ctx| ctx.wants_keyboard_input())
+    {
+        return;
+    }
+
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+    if ctrl && keys.just_pressed(KeyCode::KeyZ) {
+        events.send(if shift {
+            HistoryStep::Redo
+        } else {
+            HistoryStep::Undo
+        });
+    }
+}
+
+pub(super) fn apply_history(
+    mut events: EventReader<HistoryStep>,
+    mut history: ResMut<History>,
+    mut spawner: MapSpawner,
+    mut mapgen_settings: ResMut<MapgenSettings>,
+    mut elevation: ResMut<Elevation>,
+    mut stroke: ResMut<BrushStroke>,
+) {
+    let history = &mut *history;
+
+    for step in events.read() {
+        let edit = match step {
+            HistoryStep::Undo => history.undo.pop_back(),
+            HistoryStep::Redo => history.redo.pop(),
+        };
+        let Some(edit) = edit else {
+            continue;
+        };
+        let undo = *step == HistoryStep::Undo;
+
+        // Keep `committed` in step so `record_edits` does not record the change again
+        let Some(committed) = history.committed.as_mut() else {
+            continue;
+        };
+
+        match &edit {
+            Edit::Settings { before, after } => {
+                let settings = if undo { before } else { after };
+
+                *mapgen_settings = MapgenSettings::clone(settings);
+                history.settings = MapgenSettings::clone(settings);
+            }
+            Edit::Elevation(changes) => {
+                for &(r, before, after) in changes {
+                    let e = if undo { before } else { after };
+
+                    elevation.0[r] = e;
+                    committed.elevation[r] = e;
+                }
+
+                stroke.touch();
+            }
+            Edit::Map { before, after } => {
+                let document = if undo { before } else { after };
+
+                match MapDocument::clone(document).restore() {
+                    Ok(map) => {
+                        spawner.spawn(&document.settings, map);
+                        *mapgen_settings = document.settings.clone();
+                        history.settings = document.settings.clone();
+                        *committed = MapDocument::clone(document);
+                    }
+                    Err(err) => error!("Could not restore map: {err}"),
+                }
+            }
+        }
+
+        if undo {
+            history.redo.push(edit);
+        } else {
+            history.undo.push_back(edit);
+        }
+    }
+}
+
+/// The current map, as recorded by map edits.
+#[derive(SystemParam)]
+pub(super) struct CurrentMap<'w> {
+    settings: Res<'w, GenerationSettings>,
+    regions: Res<'w, Regions>,
+    elevation: Res<'w, Elevation>,
+}
+
+impl CurrentMap<'_> {
+    /// Keeps the settings the map was generated with, so restoring it rebuilds the same cells
+    /// even when the inspector has since moved on.
+    fn document(&self) -> MapDocument {
+        MapDocument::new(&self.settings.0, &self.regions, &self.elevation.0)
+    }
+}
+
+/// Records whatever changed since the last edit.
+pub(super) fn record_edits(
+    time: Res<Time>,
+    mut history: ResMut<History>,
+    history_settings: Res<HistorySettings>,
+    mapgen_settings: Res<MapgenSettings>,
+    map: CurrentMap,
+    stroke: Res<BrushStroke>,
+) {
+    let CurrentMap {
+        settings,
+        regions,
+        elevation,
+    } = &map;
+    let history = &mut *history;
+    history.elevation_dirty |= elevation.is_changed();
+
+    let Some(committed) = history.committed.as_mut() else {
+        history.committed = Some(map.document());
+        history.settings = mapgen_settings.clone();
+        return;
+    };
+
+    // Regenerating with settings that leave the points where they were still makes a new map
+    let map_changed = (regions.is_changed() || settings.is_changed())
+        && (committed.settings != settings.0
+            || committed.points.len() != regions.len()
+            || committed
+                .points
+                .iter()
+                .zip(&regions.points)
+                .any(|(a, b)| a[0] != b.x || a[1] != b.y));
+
+    if map_changed {
+        let current = map.document();
+        let before = std::mem::replace(committed, current.clone());
+        history.settings = mapgen_settings.clone();
+        history.elevation_dirty = false;
+
+        // The first map has nothing to go back to
+        if !before.points.is_empty() {
+            let edit = Edit::Map {
+                before: Box::new(before),
+                after: Box::new(current),
+            };
+            history.push(edit, &history_settings);
+        }
+        return;
+    }
+
+    let mut edits = Vec::new();
+
+    if history.elevation_dirty
+        && !stroke.in_progress()
+        && committed.elevation.len() == elevation.0.len()
+    {
+        history.elevation_dirty = false;
+
+        let changes: Vec<_> = committed
+            .elevation
+            .iter_mut()
+            .zip(&elevation.0)
+            .enumerate()
+            .filter(|(_, (before, after))| before != after)
+            .map(|(r, (before, &after))| {
+                let change = (r, *before, after);
+                *before = after;
+                change
+            })
+            .collect();
+
+        if !changes.is_empty() {
+            edits.push(Edit::Elevation(changes));
+        }
+    }
+
+    if *mapgen_settings != history.settings {
+        let now = time.elapsed_secs_f64();
+        let after = mapgen_settings.clone();
+        let before = std::mem::replace(&mut history.settings, after.clone());
+
+        let recent = now - history.last_settings_change < MERGE_WINDOW;
+        history.last_settings_change = now;
+
+        let merge = recent && edits.is_empty() && history.redo.is_empty();
+        match history.undo.back_mut() {
+            Some(Edit::Settings { after: last, .. }) if merge => **last = after,
+            _ => edits.push(Edit::Settings {
+                before: Box::new(before),
+                after: Box::new(after),
+            }),
+        }
+    }
+
+    for edit in edits {
+        history.push(edit, &history_settings);
+    }
+}
@@ -1,167 +1,365 @@
+mod biome;
+mod brush;
+mod document;
+mod dual_mesh;
+mod elevation;
+pub mod export;
+mod generator;
+mod history;
+mod moisture;
+mod painterly;
+mod picking;
+mod pointer;
+mod points;
+mod render;
+mod rivers;
+mod terrain;
+mod terrain_view;
 mod utils;
 
 use bevy::{
-    asset::RenderAssetUsages,
+    ecs::system::SystemParam,
     math::{vec2, vec3},
     prelude::*,
-    render::mesh::{Indices, PrimitiveTopology},
 };
+use serde::{Deserialize, Serialize};
+
+pub use biome::{Biome, BiomePalette, Biomes};
+pub use brush::{BrushSettings, BrushTool};
+pub use document::{read_document, write_document, MapDocument, MAP_VERSION};
+pub use dual_mesh::{DualMesh, RegionSides};
+pub use elevation::{ElevationSettings, FractalType, IslandShape, NoiseType};
+pub use generator::{
+    generate_map, map_bounds, restore_map, GeneratedMap, GenerationTimings, Regions,
+};
+pub use history::{History, HistorySettings};
+pub use moisture::MoistureSettings;
+pub use painterly::{PainterlyMaterial, PainterlySettings, PainterlyView};
+pub use picking::HoveredCell;
+pub use points::PointDistribution;
+pub use render::{MapMesh, RiverMesh};
+pub use rivers::{RiverSegment, RiverSettings, Rivers};
+pub use terrain::TerrainMesh;
+pub use terrain_view::{TerrainSettings, TerrainView};
 
-use delaunator::Point;
-use rand::{Rng, SeedableRng};
-use rand_chacha::ChaCha8Rng;
-use utils::assign_elevation;
-use voronoice::{BoundingBox, VoronoiBuilder};
+use biome::assign_biomes;
+use render::{cell_mesh, map_mesh, river_mesh};
 
-use crate::state::RegenCells;
+use crate::state::{DemoState, ExportMap, HistoryStep, LoadMap, MapView, RegenCells, SaveMap};
 
 #[derive(Component)]
 pub struct Cell((usize, Handle<ColorMaterial>));
 
-#[derive(Resource, Reflect)]
+/// Entities drawing the map, whether batched or one per cell
+type MapEntities = Or<(With<Cell>, With<MapMesh>, With<RiverMesh>)>;
+
+#[derive(Resource, Reflect, Serialize, Deserialize, Clone, PartialEq)]
 pub struct MapgenSettings {
-    rng_seed: u64,
-    grid_size: usize,
-    jitter: f64,
-    elevation_threshold: f64,
+    pub rng_seed: u64,
+    pub grid_size: usize,
+    pub point_distribution: PointDistribution,
+    /// How far grid points are randomly moved, in cells; anything above half a cell is
+    /// treated as half a cell, so points never leave their own cell
+    pub jitter: f64,
+    /// Run Lloyd relaxation on the points; relaxing evens out cell sizes but undoes `jitter`
+    pub relax_points: bool,
+    pub lloyd_iterations: usize,
+    pub elevation_threshold: f64,
+    /// Draw the whole map as one vertex-colored mesh instead of one entity per cell
+    pub batch_cells: bool,
+    pub elevation: ElevationSettings,
+    pub moisture: MoistureSettings,
+    pub rivers: RiverSettings,
 }
 
+impl Default for MapgenSettings {
+    fn default() -> Self {
+        MapgenSettings {
+            rng_seed: 0xDEADBEEF,
+            grid_size: 20,
+            point_distribution: PointDistribution::JitteredGrid,
+            jitter: 0.5,
+            relax_points: true,
+            lloyd_iterations: 2,
+            elevation_threshold: 0.65,
+            batch_cells: true,
+            elevation: ElevationSettings::default(),
+            moisture: MoistureSettings::default(),
+            rivers: RiverSettings::default(),
+        }
+    }
+}
+
+/// Settings the current map was generated or loaded with.
+///
+/// `MapgenSettings` follows the inspector, so it drifts from these until the map is
+/// regenerated. Anything that describes the map itself, like its bounds, reads them from here.
+#[derive(Resource, Default, Clone)]
+pub struct GenerationSettings(pub MapgenSettings);
+
+impl MapgenSettings {
+    /// Catches the settings no map can be generated from, which the inspector happily accepts.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.grid_size < 2 {
+            return Err(format!(
+                "grid size must be at least 2, got {}",
+                self.grid_size
+            ));
+        }
+        if !self.jitter.is_finite() {
+            return Err(format!(
+                "jitter must be a finite number, got {}",
+                self.jitter
+            ));
+        }
+
+        // Sea-relative elevation divides by both the threshold and its distance to 1.0
+        let threshold = self.elevation_threshold;
+        if !(threshold > 0.0 && threshold < 1.0) {
+            return Err(format!(
+                "elevation threshold must be between 0 and 1, exclusive, got {threshold}"
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct Elevation(pub Vec<f64>);
+
+/// Rainfall of each region, see [`MoistureSettings`].
 #[derive(Resource, Default)]
-pub struct Elevation(Vec<f64>);
+pub struct Moisture(pub Vec<f64>);
 
 pub struct MapgenPlugin;
 
 impl Plugin for MapgenPlugin {
     fn build(&self, app: &mut bevy::app::App) {
-        app.insert_resource(MapgenSettings {
-            rng_seed: 0xDEADBEEF,
-            grid_size: 20,
-            jitter: 1.0,
-            elevation_threshold: 0.65,
-        });
+        app.register_type::<MapgenSettings>();
+        app.register_type::<BiomePalette>();
+        app.register_type::<BrushSettings>();
+        app.register_type::<HistorySettings>();
+        app.register_type::<TerrainSettings>();
+        app.register_type::<PainterlySettings>();
+        app.register_type::<export::ExportSettings>();
+
+        app.init_resource::<MapgenSettings>();
+        app.init_resource::<GenerationSettings>();
+        app.init_resource::<BiomePalette>();
+        app.init_resource::<BrushSettings>();
+        app.init_resource::<brush::BrushStroke>();
+        app.init_resource::<History>();
+        app.init_resource::<HistorySettings>();
+        app.init_resource::<HoveredCell>();
+        app.init_resource::<TerrainSettings>();
+        app.init_resource::<PainterlySettings>();
         app.insert_resource(Elevation::default());
+        app.insert_resource(Moisture::default());
+        app.init_resource::<Biomes>();
+        app.init_resource::<Regions>();
+        app.init_resource::<DualMesh>();
+        app.init_resource::<Rivers>();
+        app.init_resource::<GenerationTimings>();
+        app.init_resource::<export::ExportSettings>();
+
+        app.add_event::<ExportMap>();
+        app.add_event::<SaveMap>();
+        app.add_event::<LoadMap>();
+        app.add_event::<HistoryStep>();
+
+        app.add_plugins(MaterialPlugin::<PainterlyMaterial>::default());
+        app.add_sub_state::<MapView>();
 
         app.add_systems(Startup, setup);
-
-        app.add_systems(Update, (gen_circles, update_height_material));
+        app.add_systems(OnEnter(MapView::Terrain), terrain_view::enter_terrain_view);
+        app.add_systems(OnExit(MapView::Terrain), terrain_view::exit_terrain_view);
+        app.add_systems(OnEnter(MapView::Painterly), painterly::enter_painterly_view);
+        app.add_systems(OnExit(MapView::Painterly), painterly::exit_painterly_view);
+
+        app.add_systems(
+            Update,
+            (
+                // Each of these may replace the map, so they run in turn and the last one wins
+                (
+                    gen_circles,
+                    document::load_map,
+                    history::history_shortcuts.run_if(in_state(DemoState::Mapgen)),
+                    history::apply_history,
+                )
+                    .chain(),
+                (
+                    brush::paint_elevation,
+                    brush::finish_stroke.run_if(brush::stroke_finished),
+                )
+                    .chain(),
+                update_biomes,
+                update_height_material,
+                history::record_edits,
+            )
+                .chain(),
+        );
+        app.add_systems(
+            Update,
+            export::export_map
+                .after(update_biomes)
+                .run_if(on_event::<ExportMap>),
+        );
+        app.add_systems(
+            Update,
+            (
+                terrain_view::sync_cameras,
+                terrain_view::update_terrain
+                    .after(update_height_material)
+                    .run_if(in_state(MapView::Terrain)),
+            ),
+        );
+        app.add_systems(
+            Update,
+            (
+                painterly::update_painterly_camera,
+                painterly::update_painterly.after(update_height_material),
+            )
+                .run_if(in_state(MapView::Painterly)),
+        );
+        app.add_systems(
+            Update,
+            (picking::pick_cell, picking::highlight_cell)
+                .chain()
+                .after(history::record_edits),
+        );
+        app.add_systems(
+            Update,
+            document::save_map
+                .after(gen_circles)
+                .run_if(on_event::<SaveMap>),
+        );
     }
 }
 
-// TODO refactor
 fn gen_circles(
-    mut commands: Commands,
     mut events: EventReader<RegenCells>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    mut elevation_res: ResMut<Elevation>,
+    mut spawner: MapSpawner,
     mapgen_settings: Res<MapgenSettings>,
-    query: Query<Entity, With<Cell>>,
 ) {
     for _ in events.read() {
-        let mut rng = ChaCha8Rng::seed_from_u64(mapgen_settings.rng_seed);
-
-        let size: usize = mapgen_settings.grid_size;
-        let mut points = Vec::<Point>::new();
-        let jitter = mapgen_settings.jitter;
-
-        let center = Point {
-            x: (size / 2) as f64,
-            y: (size / 2) as f64,
-        };
-
-        for x in 0..size {
-            for y in 0..size {
-                let offset_x = rng.random::<(f64, f64)>();
-                let offset_y = rng.random::<(f64, f64)>();
+        match generate_map(&mapgen_settings) {
+            Ok(map) => spawner.spawn(&mapgen_settings, map),
+            Err(err) => error!("Could not generate map: {err}"),
+        }
+    }
+}
 
-                let new_point = Point {
-                    x: (x as f64) + jitter * (offset_x.0 - offset_x.1),
-                    y: (y as f64) + jitter * (offset_y.0 - offset_y.1),
-                };
+/// Everything needed to replace the current map with another one.
+#[derive(SystemParam)]
+struct MapSpawner<'w, 's> {
+    commands: Commands<'w, 's>,
+    meshes: ResMut<'w, Assets<Mesh>>,
+    materials: ResMut<'w, Assets<ColorMaterial>>,
+    query: Query<'w, 's, Entity, MapEntities>,
+}
 
-                points.push(new_point);
-            }
-        }
+impl MapSpawner<'_, '_> {
+    /// Replaces the entities and resources of the current map with `map`.
+    fn spawn(&mut self, settings: &MapgenSettings, map: GeneratedMap) {
+        let MapSpawner {
+            commands,
+            meshes,
+            materials,
+            query,
+        } = self;
 
         query
             .iter()
             .for_each(|entity| commands.entity(entity).despawn());
 
-        let elevation = assign_elevation(&points, mapgen_settings.rng_seed as u32);
-
-        let my_voronoi = VoronoiBuilder::default()
-            .set_sites(points)
-            .set_bounding_box(BoundingBox::new(
-                center,
-                mapgen_settings.grid_size as f64,
-                mapgen_settings.grid_size as f64,
-            ))
-            .set_lloyd_relaxation_iterations(50)
-            .build()
-            .unwrap();
-
-        for (idx, cell) in my_voronoi.iter_cells().enumerate() {
-            let mut mesh = Mesh::new(
-                PrimitiveTopology::TriangleList,
-                RenderAssetUsages::RENDER_WORLD,
-            );
-
-            let vertices: Vec<Vec3> = cell
-                .iter_vertices()
-                .map(|vertex| Vec3 {
-                    x: vertex.x as f32,
-                    y: vertex.y as f32,
-                    z: 0.0,
-                })
-                .collect();
-
-            let mut indices = vec![];
-            for i in 1..vertices.len() {
-                indices.extend_from_slice(&[0, i as u32, i as u32 - 1]);
-            }
-
-            let color = if elevation[idx] < mapgen_settings.elevation_threshold {
-                Color::hsl(240.0, 0.3, 0.5)
-            } else {
-                Color::hsl(90.0, 0.3, 0.5)
-            };
-
-            mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, vertices);
-            mesh.insert_indices(Indices::U32(indices));
-
-            let material_handle = materials.add(ColorMaterial::from_color(color));
+        // Colors are filled in by `update_height_material` once the biomes are known
+        if settings.batch_cells {
+            let (mesh, map_mesh) = map_mesh(&map.regions);
 
             commands.spawn((
-                Cell((idx, material_handle.clone())),
+                map_mesh,
                 Mesh2d(meshes.add(mesh)),
-                MeshMaterial2d(material_handle),
+                MeshMaterial2d(materials.add(ColorMaterial::default())),
             ));
+        } else {
+            for (idx, cell) in map.regions.cells.iter().enumerate() {
+                let material_handle = materials.add(ColorMaterial::default());
+
+                commands.spawn((
+                    Cell((idx, material_handle.clone())),
+                    Mesh2d(meshes.add(cell_mesh(cell))),
+                    MeshMaterial2d(material_handle),
+                ));
+            }
         }
 
-        elevation_res.0 = elevation;
+        commands.spawn((
+            RiverMesh,
+            Mesh2d(meshes.add(river_mesh(&map.rivers, settings.rivers.width))),
+            MeshMaterial2d(materials.add(ColorMaterial::default())),
+            Transform::from_xyz(0.0, 0.0, 1.0),
+        ));
+
+        commands.insert_resource(Elevation(map.elevation));
+        commands.insert_resource(Moisture(map.moisture));
+        commands.insert_resource(map.regions);
+        commands.insert_resource(map.mesh);
+        commands.insert_resource(map.rivers);
+        commands.insert_resource(map.timings);
+        commands.insert_resource(GenerationSettings(settings.clone()));
     }
 }
 
-fn update_height_material(
-    query: Query<&Cell>,
+fn update_biomes(
     elevation: Res<Elevation>,
+    moisture: Res<Moisture>,
     mapgen_settings: Res<MapgenSettings>,
+    mut biomes: ResMut<Biomes>,
+) {
+    if !elevation.is_changed() && !moisture.is_changed() && !mapgen_settings.is_changed() {
+        return;
+    }
+
+    biomes.0 = assign_biomes(
+        &elevation.0,
+        &moisture.0,
+        mapgen_settings.elevation_threshold,
+    );
+}
+
+fn update_height_material(
+    cells: Query<&Cell>,
+    map_meshes: Query<(&MapMesh, &Mesh2d)>,
+    rivers: Query<&MeshMaterial2d<ColorMaterial>, With<RiverMesh>>,
+    biomes: Res<Biomes>,
+    palette: Res<BiomePalette>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
 ) {
-    // let elevation = assign_elevation(points, seed)
-    for cell in query.iter() {
+    if !biomes.is_changed() && !palette.is_changed() {
+        return;
+    }
+
+    let colors: Vec<Color> = biomes.0.iter().map(|&biome| palette.color(biome)).collect();
+
+    for cell in cells.iter() {
         let (idx, handle) = &cell.0;
-        let color = materials.get_mut(handle);
 
-        if let Some(material) = color {
+        if let (Some(material), Some(color)) = (materials.get_mut(handle), colors.get(*idx)) {
+            material.color = *color;
+        }
+    }
 
-            let new_color = if elevation.0[*idx] < mapgen_settings.elevation_threshold {
-                Color::hsl(240.0, 0.3, 0.5)
-            } else {
-                Color::hsl(90.0, 0.3, 0.5)
-            };
+    for (map_mesh, mesh) in map_meshes.iter() {
+        if let Some(mesh) = meshes.get_mut(&mesh.0) {
+            mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, map_mesh.vertex_colors(&colors));
+        }
+    }
 
-            material.color = new_color;
+    for handle in rivers.iter() {
+        if let Some(material) = materials.get_mut(&handle.0) {
+            material.color = palette.river;
         }
     }
 }
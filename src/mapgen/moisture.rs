@@ -0,0 +1,93 @@
+use bevy::prelude::Reflect;
+use serde::{Deserialize, Serialize};
+
+use super::{dual_mesh::DualMesh, utils::sea_relative};
+
+#[derive(Reflect, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct MoistureSettings {
+    /// Direction the wind blows towards, in degrees counter-clockwise from +x
+    pub wind_angle: f64,
+    /// Fraction of the carried humidity that falls as rain in each region
+    pub raininess: f64,
+    /// How much humidity is forced out when air is pushed over high ground
+    pub rain_shadow: f64,
+    /// Humidity picked up over the ocean, scaled by water depth
+    pub evaporation: f64,
+}
+
+impl Default for MoistureSettings {
+    fn default() -> Self {
+        MoistureSettings {
+            wind_angle: 0.0,
+            raininess: 0.9,
+            rain_shadow: 0.5,
+            evaporation: 0.5,
+        }
+    }
+}
+
+/// Computes the rainfall of every region by sweeping humidity across the map with the wind.
+///
+/// Regions are visited in wind order and inherit the average humidity of their upwind
+/// neighbours. Air over the ocean picks up moisture, and air forced over terrain higher than it
+/// can hold drops the excess as orographic rain, leaving the lee side of mountains dry.
+pub fn assign_moisture(
+    mesh: &DualMesh,
+    elevation: &[f64],
+    threshold: f64,
+    settings: &MoistureSettings,
+) -> Vec<f64> {
+    let num_regions = elevation.len();
+    let (sin, cos) = settings.wind_angle.to_radians().sin_cos();
+
+    let wind_sort: Vec<f64> = (0..num_regions)
+        .map(|r| {
+            let pos = mesh.r_pos(r);
+            pos.x * cos + pos.y * sin
+        })
+        .collect();
+
+    let mut wind_order: Vec<usize> = (0..num_regions).collect();
+    wind_order.sort_by(|&a, &b| wind_sort[a].total_cmp(&wind_sort[b]).then(a.cmp(&b)));
+
+    let mut humidity = vec![0.0; num_regions];
+    let mut rainfall = vec![0.0; num_regions];
+
+    for r in wind_order {
+        let (count, sum) = mesh
+            .r_circulate_r(r)
+            .filter(|&neighbor_r| !mesh.is_ghost_r(neighbor_r))
+            .filter(|&neighbor_r| wind_sort[neighbor_r] < wind_sort[r])
+            .fold((0, 0.0), |(count, sum), neighbor_r| {
+                (count + 1, sum + humidity[neighbor_r])
+            });
+
+        let e = sea_relative(elevation[r], threshold);
+        let mut r_humidity = 0.0;
+        let mut r_rainfall = 0.0;
+
+        if count > 0 {
+            r_humidity = sum / count as f64;
+            r_rainfall += settings.raininess * r_humidity;
+        }
+
+        if mesh.is_boundary_r(r) {
+            r_humidity = 1.0;
+        }
+
+        if e < 0.0 {
+            r_humidity += settings.evaporation * -e;
+        }
+
+        if r_humidity > 1.0 - e {
+            let orographic_rainfall = settings.rain_shadow * (r_humidity - (1.0 - e));
+            r_rainfall += settings.raininess * orographic_rainfall;
+            r_humidity -= orographic_rainfall;
+        }
+
+        humidity[r] = r_humidity;
+        rainfall[r] = r_rainfall;
+    }
+
+    rainfall
+}
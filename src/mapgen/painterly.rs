@@ -0,0 +1,313 @@
+use bevy::{
+    asset::RenderAssetUsages,
+    core_pipeline::prepass::DepthPrepass,
+    ecs::system::SystemParam,
+    image::ImageSampler,
+    prelude::*,
+    render::{
+        camera::ScalingMode,
+        render_resource::{AsBindGroup, Extent3d, ShaderRef, TextureDimension, TextureFormat},
+    },
+};
+
+use super::{
+    map_bounds, utils::sea_relative, Biome, BiomePalette, Biomes, DualMesh, Elevation,
+    GenerationSettings, Moisture, TerrainMesh, TerrainSettings,
+};
+
+const SHADER_ASSET_PATH: &str = "shaders/painterly.wgsl";
+
+/// Size of the colormap along moisture and sea-relative elevation.
+const COLORMAP_SIZE: (u32, u32) = (64, 128);
+
+/// How much darker the deepest ocean is than the coast.
+const OCEAN_DARKENING: f32 = 0.25;
+
+/// Mapgen4-style oblique rendering, see `assets/shaders/painterly.wgsl`.
+#[derive(Resource, Reflect)]
+pub struct PainterlySettings {
+    /// Angle between the view and straight down, in degrees
+    pub tilt: f32,
+    /// Rotation of the view around the vertical axis, in degrees
+    pub rotation: f32,
+    /// Darkness of the outlines, 0.0 turns them off
+    pub outline_strength: f32,
+    /// Depth jump between neighbouring pixels where outlines start, in map units; lower
+    /// values outline smaller ridges
+    pub outline_depth: f32,
+    /// Contrast of the hillshading, 0.0 turns it off
+    pub shading_strength: f32,
+}
+
+impl Default for PainterlySettings {
+    fn default() -> Self {
+        PainterlySettings {
+            tilt: 30.0,
+            rotation: 0.0,
+            outline_strength: 0.5,
+            outline_depth: 0.15,
+            shading_strength: 0.6,
+        }
+    }
+}
+
+/// Entities that only exist while the map is shown in the painterly view.
+#[derive(Component)]
+pub struct PainterlyView;
+
+#[derive(Asset, TypePath, AsBindGroup, Clone)]
+pub struct PainterlyMaterial {
+    /// Towards the light, in world space
+    #[uniform(0)]
+    pub light_direction: Vec3,
+    #[uniform(1)]
+    pub shading_strength: f32,
+    #[uniform(2)]
+    pub outline_strength: f32,
+    #[uniform(3)]
+    pub outline_depth: f32,
+    /// Biome colors, indexed by moisture along x and sea-relative elevation along y
+    #[texture(4)]
+    #[sampler(5)]
+    pub colormap: Handle<Image>,
+}
+
+impl PainterlyMaterial {
+    fn new(settings: &PainterlySettings, colormap: Handle<Image>) -> Self {
+        let mut material = PainterlyMaterial {
+            light_direction: Vec3::Y,
+            shading_strength: 0.0,
+            outline_strength: 0.0,
+            outline_depth: 0.0,
+            colormap,
+        };
+        material.apply(settings);
+
+        material
+    }
+
+    /// Copies `settings` into the uniforms.
+    fn apply(&mut self, settings: &PainterlySettings) {
+        // Light from the north-west of the screen, turning with the view like in Mapgen4
+        let rotation = Quat::from_rotation_y(settings.rotation.to_radians());
+
+        self.light_direction = rotation * Vec3::new(-1.0, 1.5, -1.0).normalize();
+        self.shading_strength = settings.shading_strength;
+        self.outline_strength = settings.outline_strength;
+        self.outline_depth = settings.outline_depth.max(f32::EPSILON);
+    }
+}
+
+// Keeps Bevy's vertex layout, so the default prepass shader can draw the mesh into the depth
+// prepass the outlines are found in
+impl Material for PainterlyMaterial {
+    fn vertex_shader() -> ShaderRef {
+        SHADER_ASSET_PATH.into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        SHADER_ASSET_PATH.into()
+    }
+}
+
+/// Bakes the biome classification into a texture, so colors blend smoothly across regions
+/// instead of changing at cell borders.
+pub fn colormap(palette: &BiomePalette) -> Image {
+    let (width, height) = COLORMAP_SIZE;
+    let mut data = Vec::with_capacity((4 * width * height) as usize);
+
+    for y in 0..height {
+        let e = 2.0 * (y as f64 + 0.5) / height as f64 - 1.0;
+
+        for x in 0..width {
+            let m = (x as f64 + 0.5) / width as f64;
+            let mut color = palette.color(Biome::classify(e, m));
+
+            if e < 0.0 {
+                color = color.darker(OCEAN_DARKENING * -e as f32);
+            }
+            data.extend_from_slice(&color.to_srgba().to_u8_array());
+        }
+    }
+
+    let mut image = Image::new(
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    image.sampler = ImageSampler::linear();
+
+    image
+}
+
+/// Orientation of the painterly camera, looking down at the map from `tilt` degrees off
+/// vertical.
+fn view_rotation(settings: &PainterlySettings) -> Quat {
+    Quat::from_euler(
+        EulerRot::YXZ,
+        settings.rotation.to_radians(),
+        -(90.0 - settings.tilt.clamp(0.0, 89.0)).to_radians(),
+        0.0,
+    )
+}
+
+/// The map's y axis runs along -z in 3D, see `TerrainMesh`.
+fn view_transform(settings: &PainterlySettings, grid_size: usize) -> Transform {
+    let bounds = map_bounds(grid_size);
+    let center = bounds.center();
+    let target = Vec3::new(center.x as f32, 0.0, -center.y as f32);
+    let rotation = view_rotation(settings);
+
+    // Far enough back that the highest peaks stay in front of the near plane
+    let distance = 2.0 * bounds.width() as f32;
+
+    Transform::from_translation(target + rotation * Vec3::Z * distance).with_rotation(rotation)
+}
+
+pub(super) fn enter_painterly_view(
+    mut commands: Commands,
+    generation_settings: Res<GenerationSettings>,
+    settings: Res<PainterlySettings>,
+) {
+    let grid_size = generation_settings.0.grid_size;
+    let bounds = map_bounds(grid_size);
+
+    commands.spawn((
+        PainterlyView,
+        Camera3d::default(),
+        // Read back by the material to outline ridges
+        DepthPrepass,
+        Projection::Orthographic(OrthographicProjection {
+            scaling_mode: ScalingMode::FixedVertical {
+                viewport_height: 1.2 * bounds.height() as f32,
+            },
+            ..OrthographicProjection::default_3d()
+        }),
+        view_transform(&settings, grid_size),
+    ));
+}
+
+pub(super) fn exit_painterly_view(
+    mut commands: Commands,
+    query: Query<Entity, With<PainterlyView>>,
+) {
+    query
+        .iter()
+        .for_each(|entity| commands.entity(entity).despawn());
+}
+
+pub(super) fn update_painterly_camera(
+    settings: Res<PainterlySettings>,
+    generation_settings: Res<GenerationSettings>,
+    mut cameras: Query<&mut Transform, (With<PainterlyView>, With<Camera3d>)>,
+) {
+    if !settings.is_changed() && !generation_settings.is_changed() {
+        return;
+    }
+
+    for mut transform in cameras.iter_mut() {
+        *transform = view_transform(&settings, generation_settings.0.grid_size);
+    }
+}
+
+/// Keeps the painterly mesh and material in step with the map, spawning them on the first
+/// frame of the view.
+pub(super) fn update_painterly(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<PainterlyMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    views: Query<(&Mesh3d, &MeshMaterial3d<PainterlyMaterial>), With<PainterlyView>>,
+    map: PainterlyInputs,
+    settings: Res<PainterlySettings>,
+) {
+    let map_changed = map.biomes.is_changed() || map.terrain_settings.is_changed();
+    let material_changed = map.palette.is_changed() || settings.is_changed();
+
+    let view = views.get_single().ok();
+    if view.is_some() && !map_changed && !material_changed {
+        return;
+    }
+
+    let Some((mesh_handle, material_handle)) = view else {
+        let material = PainterlyMaterial::new(&settings, images.add(colormap(&map.palette)));
+
+        commands.spawn((
+            PainterlyView,
+            Mesh3d(meshes.add(map.to_mesh())),
+            MeshMaterial3d(materials.add(material)),
+        ));
+        return;
+    };
+
+    if map_changed {
+        meshes.insert(&mesh_handle.0, map.to_mesh());
+    }
+
+    if material_changed {
+        if let Some(material) = materials.get_mut(&material_handle.0) {
+            material.apply(&settings);
+
+            if map.palette.is_changed() {
+                images.insert(&material.colormap, colormap(&map.palette));
+            }
+        }
+    }
+}
+
+/// The parts of the map the painterly view is built from.
+#[derive(SystemParam)]
+pub(super) struct PainterlyInputs<'w> {
+    mesh: Res<'w, DualMesh>,
+    elevation: Res<'w, Elevation>,
+    moisture: Res<'w, Moisture>,
+    biomes: Res<'w, Biomes>,
+    palette: Res<'w, BiomePalette>,
+    settings: Res<'w, GenerationSettings>,
+    terrain_settings: Res<'w, TerrainSettings>,
+}
+
+impl PainterlyInputs<'_> {
+    /// The terrain mesh, with the colormap coordinates of each region as its UVs.
+    fn to_mesh(&self) -> Mesh {
+        let threshold = self.settings.0.elevation_threshold;
+        let mut terrain = TerrainMesh::new(
+            &self.mesh,
+            &self.elevation.0,
+            &[],
+            threshold,
+            self.terrain_settings.height,
+        );
+
+        // Water is a flat surface at sea level. Done here rather than in the vertex shader so
+        // the depth prepass sees the same surface.
+        for position in &mut terrain.positions {
+            position[1] = position[1].max(0.0);
+        }
+
+        let mut mesh = terrain.to_mesh();
+
+        let lookup: Vec<[f32; 2]> = self
+            .elevation
+            .0
+            .iter()
+            .zip(&self.moisture.0)
+            .map(|(&e, &m)| {
+                let e = (sea_relative(e, threshold) + 1.0) / 2.0;
+                [m.clamp(0.0, 1.0) as f32, e.clamp(0.0, 1.0) as f32]
+            })
+            .collect();
+
+        // Colors come from the colormap instead
+        mesh.remove_attribute(Mesh::ATTRIBUTE_COLOR);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, lookup);
+
+        mesh
+    }
+}
@@ -0,0 +1,50 @@
+use bevy::prelude::*;
+
+use super::{pointer::MapPointer, utils::cell_contains, Regions};
+
+/// Outline drawn around the hovered cell.
+const HIGHLIGHT_COLOR: Color = Color::WHITE;
+
+/// Region under the cursor, if any.
+///
+/// Kept while the cursor is over the UI, so the cell can still be inspected there.
+#[derive(Resource, Default)]
+pub struct HoveredCell(pub Option<usize>);
+
+pub(super) fn pick_cell(
+    mut pointer: MapPointer,
+    regions: Res<Regions>,
+    mut hovered: ResMut<HoveredCell>,
+) {
+    if pointer.over_ui() {
+        return;
+    }
+
+    let region = pointer.position().and_then(|cursor| {
+        let (x, y) = (cursor.x as f64, cursor.y as f64);
+
+        // The cell of the nearest site is the one under the cursor, unless it is off the map.
+        // The hovered cell is usually next to it, so the search starts there.
+        let nearest = regions.nearest(x, y, hovered.0.unwrap_or(0))?;
+
+        cell_contains(&regions.cells[nearest], x, y).then_some(nearest)
+    });
+
+    // Only write on change, so the UI is not told about a new cell every frame
+    if hovered.0 != region {
+        hovered.0 = region;
+    }
+}
+
+pub(super) fn highlight_cell(hovered: Res<HoveredCell>, regions: Res<Regions>, mut gizmos: Gizmos) {
+    let Some(cell) = hovered.0.and_then(|r| regions.cells.get(r)) else {
+        return;
+    };
+
+    let outline = cell
+        .iter()
+        .chain(cell.first())
+        .map(|p| Vec2::new(p.x as f32, p.y as f32));
+
+    gizmos.linestrip_2d(outline, HIGHLIGHT_COLOR);
+}
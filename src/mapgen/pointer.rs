@@ -0,0 +1,39 @@
+use bevy::{ecs::system::SystemParam, prelude::*, window::PrimaryWindow};
+use bevy_inspector_egui::bevy_egui::EguiContexts;
+
+/// The mouse as seen by the map: its position in world space and its buttons, both ignored
+/// while the pointer is over an egui window.
+#[derive(SystemParam)]
+pub(super) struct MapPointer<'w, 's> {
+    windows: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+    cameras: Query<'w, 's, (&'static Camera, &'static GlobalTransform), With<Camera2d>>,
+    buttons: Res<'w, ButtonInput<MouseButton>>,
+    egui: EguiContexts<'w, 's>,
+}
+
+impl MapPointer<'_, '_> {
+    /// Position of the cursor on the map, if it is over the map rather than the UI.
+    pub fn position(&mut self) -> Option<Vec2> {
+        if self.over_ui() {
+            return None;
+        }
+
+        let cursor = self.windows.get_single().ok()?.cursor_position()?;
+        let (camera, transform) = self.cameras.get_single().ok()?;
+        if !camera.is_active {
+            return None;
+        }
+
+        camera.viewport_to_world_2d(transform, cursor).ok()
+    }
+
+    pub fn pressed(&mut self, button: MouseButton) -> bool {
+        self.buttons.pressed(button) && !self.over_ui()
+    }
+
+    pub fn over_ui(&mut self) -> bool {
+        self.egui
+            .try_ctx_mut()
+            .is_some_and(|ctx| ctx.wants_pointer_input() || ctx.is_pointer_over_area())
+    }
+}
@@ -0,0 +1,272 @@
+use std::f64::consts::TAU;
+
+use bevy::prelude::Reflect;
+use rand::Rng;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use voronoice::{BoundingBox, Point};
+
+/// How region sites are scattered over the map before relaxation.
+#[derive(Reflect, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PointDistribution {
+    /// One point per grid cell, offset by up to `jitter` cells
+    JitteredGrid,
+    /// Evenly spaced random points, with a ring of points along the map edges
+    PoissonDisc,
+    /// Staggered rows, offset by up to `jitter` cells
+    HexGrid,
+    /// Uniformly random points
+    Random,
+}
+
+/// Minimum distance between Poisson disc samples, chosen so a map gets about as many points
+/// as the jittered grid.
+const POISSON_RADIUS: f64 = 0.85;
+
+/// Candidates tried around each active sample before it is retired.
+const POISSON_ATTEMPTS: usize = 30;
+
+/// Most a grid point is moved, in cells. Each point stays inside its own cell, so no two
+/// points can meet however large `jitter` is set.
+const MAX_JITTER: f64 = 0.5;
+
+/// How far jittered points are kept from the map edges.
+const EDGE_INSET: f64 = 1e-3;
+
+/// Scatters roughly `size * size` points over `bounds`.
+pub fn generate_points(
+    distribution: PointDistribution,
+    size: usize,
+    jitter: f64,
+    bounds: &BoundingBox,
+    rng: &mut ChaCha8Rng,
+) -> Vec<Point> {
+    match distribution {
+        PointDistribution::JitteredGrid => jittered_grid(size, jitter, bounds, rng),
+        PointDistribution::PoissonDisc => poisson_disc(bounds, rng),
+        PointDistribution::HexGrid => hex_grid(size, jitter, bounds, rng),
+        PointDistribution::Random => random(size, bounds, rng),
+    }
+}
+
+fn jittered_grid(
+    size: usize,
+    jitter: f64,
+    bounds: &BoundingBox,
+    rng: &mut ChaCha8Rng,
+) -> Vec<Point> {
+    let (min, max) = corners(bounds);
+    let cell_width = (max.x - min.x) / size as f64;
+    let cell_height = (max.y - min.y) / size as f64;
+    let jitter = jitter.abs().min(MAX_JITTER);
+    let mut points = Vec::with_capacity(size * size);
+
+    for x in 0..size {
+        for y in 0..size {
+            let center = Point {
+                x: min.x + (x as f64 + 0.5) * cell_width,
+                y: min.y + (y as f64 + 0.5) * cell_height,
+            };
+            let offset = (jitter * cell_width, jitter * cell_height);
+            points.push(jittered(center, offset, bounds, rng));
+        }
+    }
+
+    points
+}
+
+fn hex_grid(size: usize, jitter: f64, bounds: &BoundingBox, rng: &mut ChaCha8Rng) -> Vec<Point> {
+    let (min, max) = corners(bounds);
+    let spacing = (max.x - min.x) / size as f64;
+    let row_height = spacing * f64::sqrt(3.0) / 2.0;
+    let rows = ((max.y - min.y) / row_height).floor() as usize;
+    let jitter = jitter.abs().min(MAX_JITTER);
+    let mut points = Vec::with_capacity(size * rows);
+
+    for row in 0..rows {
+        // Rows alternate between a quarter and three quarters of a step in from the left edge
+        let offset = if row % 2 == 0 { 0.25 } else { 0.75 };
+
+        for x in 0..size {
+            let center = Point {
+                x: min.x + (x as f64 + offset) * spacing,
+                y: min.y + (row as f64 + 0.5) * row_height,
+            };
+            let offset = (jitter * spacing, jitter * row_height);
+            points.push(jittered(center, offset, bounds, rng));
+        }
+    }
+
+    points
+}
+
+/// Offsets `center` by less than `max_offset` along each axis, keeping the point inside
+/// `bounds` so it isn't dropped from the diagram.
+///
+/// Only the staggered ends of hex grid rows can cross the bounds. Rows never overlap, so the
+/// clamped points of different rows stay apart.
+fn jittered(
+    center: Point,
+    max_offset: (f64, f64),
+    bounds: &BoundingBox,
+    rng: &mut ChaCha8Rng,
+) -> Point {
+    let (min, max) = corners(bounds);
+    let offset_x = rng.random::<(f64, f64)>();
+    let offset_y = rng.random::<(f64, f64)>();
+
+    Point {
+        x: (center.x + max_offset.0 * (offset_x.0 - offset_x.1))
+            .clamp(min.x + EDGE_INSET, max.x - EDGE_INSET),
+        y: (center.y + max_offset.1 * (offset_y.0 - offset_y.1))
+            .clamp(min.y + EDGE_INSET, max.y - EDGE_INSET),
+    }
+}
+
+fn random(size: usize, bounds: &BoundingBox, rng: &mut ChaCha8Rng) -> Vec<Point> {
+    let (min, max) = corners(bounds);
+
+    (0..size * size)
+        .map(|_| Point {
+            x: rng.random_range(min.x..max.x),
+            y: rng.random_range(min.y..max.y),
+        })
+        .collect()
+}
+
+/// Bridson's Poisson disc sampling, seeded with evenly spaced points along the map edges so
+/// border cells come out the same size as interior ones.
+fn poisson_disc(bounds: &BoundingBox, rng: &mut ChaCha8Rng) -> Vec<Point> {
+    let (min, max) = corners(bounds);
+    let radius = POISSON_RADIUS;
+    let cell_size = radius / f64::sqrt(2.0);
+    let columns = ((max.x - min.x) / cell_size).ceil() as usize + 1;
+    let rows = ((max.y - min.y) / cell_size).ceil() as usize + 1;
+
+    let mut grid = vec![usize::MAX; columns * rows];
+    let mut points = Vec::<Point>::new();
+    let mut active = Vec::<usize>::new();
+
+    let grid_index = |p: &Point| {
+        let column = ((p.x - min.x) / cell_size) as usize;
+        let row = ((p.y - min.y) / cell_size) as usize;
+        row * columns + column
+    };
+
+    let fits = |p: &Point, points: &[Point], grid: &[usize]| {
+        let column = ((p.x - min.x) / cell_size) as isize;
+        let row = ((p.y - min.y) / cell_size) as isize;
+
+        for y in (row - 2).max(0)..=(row + 2).min(rows as isize - 1) {
+            for x in (column - 2).max(0)..=(column + 2).min(columns as isize - 1) {
+                let other = grid[y as usize * columns + x as usize];
+                if other != usize::MAX {
+                    let q = &points[other];
+                    if (p.x - q.x).powi(2) + (p.y - q.y).powi(2) < radius * radius {
+                        return false;
+                    }
+                }
+            }
+        }
+
+        true
+    };
+
+    // Boundary points, inset slightly so they stay inside the clipping box
+    let inset = 0.1 * radius;
+    let width = max.x - min.x - 2.0 * inset;
+    let height = max.y - min.y - 2.0 * inset;
+    let steps_x = (width / radius).floor().max(1.0) as usize;
+    let steps_y = (height / radius).floor().max(1.0) as usize;
+    let mut boundary = Vec::with_capacity(2 * (steps_x + steps_y));
+    for i in 0..steps_x {
+        let x = min.x + inset + width * i as f64 / steps_x as f64;
+        boundary.push(Point {
+            x,
+            y: min.y + inset,
+        });
+        boundary.push(Point {
+            x: max.x - inset - width * i as f64 / steps_x as f64,
+            y: max.y - inset,
+        });
+    }
+    for i in 0..steps_y {
+        let y = min.y + inset + height * i as f64 / steps_y as f64;
+        boundary.push(Point {
+            x: max.x - inset,
+            y,
+        });
+        boundary.push(Point {
+            x: min.x + inset,
+            y: max.y - inset - height * i as f64 / steps_y as f64,
+        });
+    }
+
+    for p in boundary {
+        if fits(&p, &points, &grid) {
+            grid[grid_index(&p)] = points.len();
+            active.push(points.len());
+            points.push(p);
+        }
+    }
+
+    let first = Point {
+        x: rng.random_range(min.x..max.x),
+        y: rng.random_range(min.y..max.y),
+    };
+    if fits(&first, &points, &grid) {
+        grid[grid_index(&first)] = points.len();
+        active.push(points.len());
+        points.push(first);
+    }
+
+    while !active.is_empty() {
+        let i = rng.random_range(0..active.len());
+        let center = points[active[i]].clone();
+        let mut found = false;
+
+        for _ in 0..POISSON_ATTEMPTS {
+            let angle = rng.random_range(0.0..TAU);
+            let distance = rng.random_range(radius..2.0 * radius);
+            let candidate = Point {
+                x: center.x + distance * angle.cos(),
+                y: center.y + distance * angle.sin(),
+            };
+
+            let inside = candidate.x >= min.x
+                && candidate.x < max.x
+                && candidate.y >= min.y
+                && candidate.y < max.y;
+
+            if inside && fits(&candidate, &points, &grid) {
+                grid[grid_index(&candidate)] = points.len();
+                active.push(points.len());
+                points.push(candidate);
+                found = true;
+                break;
+            }
+        }
+
+        if !found {
+            active.swap_remove(i);
+        }
+    }
+
+    points
+}
+
+fn corners(bounds: &BoundingBox) -> (Point, Point) {
+    let center = bounds.center();
+    let (half_width, half_height) = (bounds.width() / 2.0, bounds.height() / 2.0);
+
+    (
+        Point {
+            x: center.x - half_width,
+            y: center.y - half_height,
+        },
+        Point {
+            x: center.x + half_width,
+            y: center.y + half_height,
+        },
+    )
+}
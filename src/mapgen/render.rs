@@ -0,0 +1,127 @@
+use bevy::{
+    asset::RenderAssetUsages,
+    prelude::*,
+    render::mesh::{Indices, PrimitiveTopology},
+};
+use delaunator::Point;
+
+use super::{rivers::Rivers, Regions};
+
+/// Marks the single entity drawing every region of the map when cells are batched.
+///
+/// Cell `r` owns the vertices `vertex_offsets[r]..vertex_offsets[r + 1]` of the mesh, so a
+/// region can be recolored by rewriting that slice of `Mesh::ATTRIBUTE_COLOR`.
+#[derive(Component)]
+pub struct MapMesh {
+    pub vertex_offsets: Vec<usize>,
+}
+
+impl MapMesh {
+    /// Per-vertex colors for `region_colors`, in the layout expected by the batched mesh.
+    ///
+    /// Colors past the last cell are ignored.
+    pub fn vertex_colors(&self, region_colors: &[Color]) -> Vec<[f32; 4]> {
+        let mut colors = Vec::with_capacity(self.vertex_offsets.last().copied().unwrap_or(0));
+
+        for (range, color) in self.vertex_offsets.windows(2).zip(region_colors) {
+            let color = LinearRgba::from(*color).to_f32_array();
+            colors.extend(std::iter::repeat_n(color, range[1] - range[0]));
+        }
+
+        colors
+    }
+}
+
+/// Triangle fan mesh for a single Voronoi cell.
+pub fn cell_mesh(cell: &[Point]) -> Mesh {
+    let mut mesh = Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+
+    let vertices: Vec<Vec3> = cell.iter().map(to_vertex).collect();
+    let indices = fan_indices(0, vertices.len());
+
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, vertices);
+    mesh.insert_indices(Indices::U32(indices));
+
+    mesh
+}
+
+/// One mesh containing the triangle fans of every cell, colored per vertex.
+///
+/// Vertices start out white; the mesh is kept in the main world so its colors can be updated in
+/// place with [`MapMesh::vertex_colors`].
+pub fn map_mesh(regions: &Regions) -> (Mesh, MapMesh) {
+    let mut mesh = Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    );
+
+    let mut vertices = Vec::<Vec3>::new();
+    let mut indices = Vec::<u32>::new();
+    let mut vertex_offsets = vec![0];
+
+    for cell in &regions.cells {
+        indices.extend(fan_indices(vertices.len(), cell.len()));
+        vertices.extend(cell.iter().map(to_vertex));
+        vertex_offsets.push(vertices.len());
+    }
+
+    let colors = vec![LinearRgba::WHITE.to_f32_array(); vertices.len()];
+
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, vertices);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    mesh.insert_indices(Indices::U32(indices));
+
+    (mesh, MapMesh { vertex_offsets })
+}
+
+/// Marks the entity drawing the rivers on top of the map.
+#[derive(Component)]
+pub struct RiverMesh;
+
+/// Quads for every river segment, `width` wide per square root unit of flow.
+pub fn river_mesh(rivers: &Rivers, width: f32) -> Mesh {
+    let mut mesh = Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+
+    let mut vertices = Vec::<Vec3>::with_capacity(4 * rivers.segments.len());
+    let mut indices = Vec::<u32>::with_capacity(6 * rivers.segments.len());
+
+    for segment in &rivers.segments {
+        let from = to_vertex(&segment.from).truncate();
+        let to = to_vertex(&segment.to).truncate();
+        let half_width = 0.5 * width * (segment.flow as f32).sqrt();
+        let side = (to - from).perp().normalize_or_zero() * half_width;
+
+        let first = vertices.len() as u32;
+        vertices.extend([from - side, from + side, to + side, to - side].map(|v| v.extend(0.0)));
+        indices.extend_from_slice(&[first, first + 1, first + 2, first, first + 2, first + 3]);
+    }
+
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, vertices);
+    mesh.insert_indices(Indices::U32(indices));
+
+    mesh
+}
+
+fn to_vertex(vertex: &Point) -> Vec3 {
+    Vec3 {
+        x: vertex.x as f32,
+        y: vertex.y as f32,
+        z: 0.0,
+    }
+}
+
+fn fan_indices(first: usize, count: usize) -> Vec<u32> {
+    let first = first as u32;
+    let mut indices = vec![];
+    for i in 1..count as u32 {
+        indices.extend_from_slice(&[first, first + i, first + i - 1]);
+    }
+
+    indices
+}
@@ -0,0 +1,230 @@
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+use bevy::prelude::{Reflect, Resource};
+use delaunator::{Point, EMPTY};
+use serde::{Deserialize, Serialize};
+
+use super::{dual_mesh::DualMesh, utils::sea_relative};
+
+#[derive(Reflect, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct RiverSettings {
+    /// Number of river systems to keep, largest first
+    pub num_rivers: usize,
+    /// Flow a triangle needs to accumulate before it is drawn as a river
+    pub min_flow: f64,
+    /// River width per square root unit of flow
+    pub width: f32,
+}
+
+impl Default for RiverSettings {
+    fn default() -> Self {
+        RiverSettings {
+            num_rivers: 30,
+            min_flow: 5.0,
+            width: 0.03,
+        }
+    }
+}
+
+/// A piece of river running between the Voronoi vertices of two adjacent triangles.
+#[derive(Clone)]
+pub struct RiverSegment {
+    pub from: Point,
+    pub to: Point,
+    pub flow: f64,
+}
+
+/// Water flow over the triangles of the [`DualMesh`], Mapgen4 style.
+#[derive(Resource, Default, Clone)]
+pub struct Rivers {
+    /// Side each triangle drains through, `EMPTY` for sinks
+    pub t_downslope_s: Vec<usize>,
+    /// Water accumulated by each triangle, including everything flowing into it
+    pub t_flow: Vec<f64>,
+    /// Water crossing each side
+    pub s_flow: Vec<f64>,
+    /// Segments carrying at least `min_flow` within the kept river systems
+    pub segments: Vec<RiverSegment>,
+}
+
+/// Routes rainfall downhill over the triangles of `mesh` and extracts river segments.
+///
+/// Triangles take the average sea-relative elevation of their corners; ghost triangles count as
+/// open ocean. Downslope directions are assigned by flooding outwards from the ocean in order of
+/// elevation, so every land triangle drains somewhere even across local minima.
+pub fn assign_rivers(
+    mesh: &DualMesh,
+    elevation: &[f64],
+    rainfall: &[f64],
+    threshold: f64,
+    settings: &RiverSettings,
+) -> Rivers {
+    let num_triangles = mesh.num_triangles();
+
+    let t_elevation: Vec<f64> = (0..num_triangles)
+        .map(|t| {
+            if mesh.is_ghost_t(t) {
+                return -1.0;
+            }
+
+            mesh.t_circulate_r(t)
+                .iter()
+                .map(|&r| sea_relative(elevation[r], threshold))
+                .sum::<f64>()
+                / 3.0
+        })
+        .collect();
+
+    let (t_downslope_s, order_t) = assign_downslope(mesh, &t_elevation);
+
+    let mut t_flow: Vec<f64> = (0..num_triangles)
+        .map(|t| {
+            if t_elevation[t] < 0.0 {
+                return 0.0;
+            }
+
+            mesh.t_circulate_r(t)
+                .iter()
+                .map(|&r| rainfall[r])
+                .sum::<f64>()
+                / 3.0
+        })
+        .collect();
+    let mut s_flow = vec![0.0; mesh.num_sides()];
+
+    for &tributary_t in order_t.iter().rev() {
+        let flow_s = t_downslope_s[tributary_t];
+        if flow_s == EMPTY {
+            continue;
+        }
+
+        let trunk_t = mesh.s_outer_t(flow_s);
+        t_flow[trunk_t] += t_flow[tributary_t];
+        s_flow[flow_s] += t_flow[tributary_t];
+    }
+
+    // The triangle each land triangle eventually drains into the ocean through
+    let mut t_mouth = vec![EMPTY; num_triangles];
+    let mut mouths = Vec::new();
+    for &t in &order_t {
+        let flow_s = t_downslope_s[t];
+        if t_elevation[t] < 0.0 || flow_s == EMPTY {
+            continue;
+        }
+
+        let trunk_t = mesh.s_outer_t(flow_s);
+        if t_elevation[trunk_t] < 0.0 {
+            t_mouth[t] = t;
+            mouths.push(t);
+        } else {
+            t_mouth[t] = t_mouth[trunk_t];
+        }
+    }
+
+    mouths.sort_by(|&a, &b| t_flow[b].total_cmp(&t_flow[a]).then(a.cmp(&b)));
+    let mut kept = vec![false; num_triangles];
+    for &t in mouths.iter().take(settings.num_rivers) {
+        kept[t] = true;
+    }
+
+    let segments = (0..num_triangles)
+        .filter(|&t| t_mouth[t] != EMPTY && kept[t_mouth[t]])
+        .filter(|&t| t_flow[t] >= settings.min_flow)
+        .map(|t| {
+            let flow_s = t_downslope_s[t];
+
+            RiverSegment {
+                from: mesh.t_pos(t).clone(),
+                to: mesh.t_pos(mesh.s_outer_t(flow_s)).clone(),
+                flow: t_flow[t],
+            }
+        })
+        .collect();
+
+    Rivers {
+        t_downslope_s,
+        t_flow,
+        s_flow,
+        segments,
+    }
+}
+
+/// Assigns each triangle the side it drains through and returns the order triangles were
+/// reached in, starting from the ocean.
+fn assign_downslope(mesh: &DualMesh, t_elevation: &[f64]) -> (Vec<usize>, Vec<usize>) {
+    let num_triangles = mesh.num_triangles();
+    let mut t_downslope_s = vec![EMPTY; num_triangles];
+    let mut visited = vec![false; num_triangles];
+    let mut order_t = Vec::with_capacity(num_triangles);
+    let mut queue = BinaryHeap::new();
+
+    for t in 0..num_triangles {
+        if t_elevation[t] >= 0.0 {
+            continue;
+        }
+
+        let mut best_e = t_elevation[t];
+        for s in mesh.t_circulate_s(t) {
+            let e = t_elevation[mesh.s_outer_t(s)];
+            if e < best_e {
+                best_e = e;
+                t_downslope_s[t] = s;
+            }
+        }
+
+        visited[t] = true;
+        order_t.push(t);
+        queue.push(QueueEntry {
+            elevation: t_elevation[t],
+            t,
+        });
+    }
+
+    while let Some(QueueEntry { t, .. }) = queue.pop() {
+        for s in mesh.t_circulate_s(t) {
+            let neighbor_t = mesh.s_outer_t(s);
+            if visited[neighbor_t] {
+                continue;
+            }
+
+            visited[neighbor_t] = true;
+            t_downslope_s[neighbor_t] = mesh.s_opposite_s(s);
+            order_t.push(neighbor_t);
+            queue.push(QueueEntry {
+                elevation: t_elevation[neighbor_t],
+                t: neighbor_t,
+            });
+        }
+    }
+
+    (t_downslope_s, order_t)
+}
+
+/// Min-heap entry ordered by elevation, ties broken by triangle index for determinism.
+struct QueueEntry {
+    elevation: f64,
+    t: usize,
+}
+
+impl Ord for QueueEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .elevation
+            .total_cmp(&self.elevation)
+            .then(other.t.cmp(&self.t))
+    }
+}
+
+impl PartialOrd for QueueEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for QueueEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for QueueEntry {}
@@ -0,0 +1,91 @@
+use bevy::{
+    asset::RenderAssetUsages,
+    prelude::*,
+    render::mesh::{Indices, PrimitiveTopology},
+};
+
+use super::{dual_mesh::DualMesh, utils::sea_relative};
+
+/// Smooth 3D terrain over the Delaunay triangles, one vertex per region.
+///
+/// The map lies in the XZ plane with `y` up: map `x` becomes `x`, map `y` becomes `-z` and
+/// sea-relative elevation, scaled by `height`, becomes `y`.
+#[derive(Clone, Default)]
+pub struct TerrainMesh {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    /// Linear RGBA
+    pub colors: Vec<[f32; 4]>,
+    /// Counter-clockwise when seen from above
+    pub indices: Vec<u32>,
+}
+
+impl TerrainMesh {
+    pub fn new(
+        mesh: &DualMesh,
+        elevation: &[f64],
+        region_colors: &[Color],
+        threshold: f64,
+        height: f32,
+    ) -> Self {
+        let positions: Vec<Vec3> = elevation
+            .iter()
+            .enumerate()
+            .map(|(r, &e)| {
+                let p = mesh.r_pos(r);
+                let y = sea_relative(e, threshold) as f32 * height;
+
+                Vec3::new(p.x as f32, y, -p.y as f32)
+            })
+            .collect();
+
+        let mut normals = vec![Vec3::ZERO; positions.len()];
+        let mut indices = Vec::with_capacity(3 * mesh.num_solid_triangles());
+
+        for t in 0..mesh.num_solid_triangles() {
+            let [a, b, c] = mesh.t_circulate_r(t);
+            let mut normal = (positions[b] - positions[a]).cross(positions[c] - positions[a]);
+
+            // Face upwards whatever the winding of the triangulation
+            let (b, c) = if normal.y < 0.0 {
+                normal = -normal;
+                (c, b)
+            } else {
+                (b, c)
+            };
+
+            // Unnormalized, so larger triangles weigh more in the vertex normals
+            for r in [a, b, c] {
+                normals[r] += normal;
+            }
+            indices.extend_from_slice(&[a as u32, b as u32, c as u32]);
+        }
+
+        TerrainMesh {
+            positions: positions.iter().map(|p| p.to_array()).collect(),
+            normals: normals
+                .iter()
+                .map(|n| n.try_normalize().unwrap_or(Vec3::Y).to_array())
+                .collect(),
+            colors: region_colors
+                .iter()
+                .map(|&color| LinearRgba::from(color).to_f32_array())
+                .collect(),
+            indices,
+        }
+    }
+
+    pub fn to_mesh(&self) -> Mesh {
+        let mut mesh = Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        );
+
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, self.positions.clone());
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, self.normals.clone());
+        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, self.colors.clone());
+        mesh.insert_indices(Indices::U32(self.indices.clone()));
+
+        mesh
+    }
+}
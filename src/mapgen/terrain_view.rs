@@ -0,0 +1,137 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use super::{
+    map_bounds, BiomePalette, Biomes, DualMesh, Elevation, GenerationSettings, TerrainMesh,
+};
+use crate::{camera::CameraState, state::MapView};
+
+#[derive(Resource, Reflect)]
+pub struct TerrainSettings {
+    /// Height of the highest peak above sea level, in map units
+    pub height: f32,
+}
+
+impl Default for TerrainSettings {
+    fn default() -> Self {
+        TerrainSettings { height: 2.0 }
+    }
+}
+
+/// Angle of the sun above the horizon, in degrees.
+const SUN_ELEVATION: f32 = 45.0;
+
+/// Entities that only exist while the map is shown as 3D terrain.
+#[derive(Component)]
+pub struct TerrainView;
+
+/// Only the camera of the current view renders. The painterly view spawns its own camera, and
+/// outside the mapgen demo the pan-orbit camera shows the renderer demo.
+pub(super) fn sync_cameras(
+    view: Option<Res<State<MapView>>>,
+    mut flat: Query<&mut Camera, With<Camera2d>>,
+    mut orbit: Query<&mut Camera, (With<CameraState>, Without<Camera2d>)>,
+) {
+    let view = view.map(|view| *view.get());
+    let flat_active = view == Some(MapView::Flat);
+    let orbit_active = matches!(view, None | Some(MapView::Terrain));
+
+    // Compared first so the cameras are not marked as changed every frame
+    for mut camera in flat.iter_mut() {
+        if camera.is_active != flat_active {
+            camera.is_active = flat_active;
+        }
+    }
+    for mut camera in orbit.iter_mut() {
+        if camera.is_active != orbit_active {
+            camera.is_active = orbit_active;
+        }
+    }
+}
+
+pub(super) fn enter_terrain_view(
+    mut commands: Commands,
+    generation_settings: Res<GenerationSettings>,
+    mut cameras: Query<&mut CameraState>,
+) {
+    let bounds = map_bounds(generation_settings.0.grid_size);
+    let center = bounds.center();
+
+    // The map's y axis runs along -z in the terrain, see `TerrainMesh`
+    for mut camera in cameras.iter_mut() {
+        camera.center = Vec3::new(center.x as f32, 0.0, -center.y as f32);
+        camera.radius = 1.2 * bounds.width() as f32;
+        camera.pitch = -35f32.to_radians();
+        camera.yaw = 0.0;
+    }
+
+    commands.spawn((
+        TerrainView,
+        DirectionalLight {
+            shadows_enabled: true,
+            ..default()
+        },
+        Transform::from_rotation(Quat::from_euler(
+            EulerRot::YXZ,
+            30f32.to_radians(),
+            -SUN_ELEVATION.to_radians(),
+            0.0,
+        )),
+    ));
+}
+
+pub(super) fn exit_terrain_view(mut commands: Commands, query: Query<Entity, With<TerrainView>>) {
+    query
+        .iter()
+        .for_each(|entity| commands.entity(entity).despawn());
+}
+
+/// Keeps the terrain mesh in step with the map, spawning it on the first frame of the view.
+pub(super) fn update_terrain(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    terrains: Query<&Mesh3d, With<TerrainView>>,
+    map: TerrainInputs,
+    terrain_settings: Res<TerrainSettings>,
+) {
+    let changed =
+        map.biomes.is_changed() || map.palette.is_changed() || terrain_settings.is_changed();
+
+    if !changed && !terrains.is_empty() {
+        return;
+    }
+
+    let colors: Vec<Color> = map.biomes.0.iter().map(|&b| map.palette.color(b)).collect();
+    let terrain = TerrainMesh::new(
+        &map.mesh,
+        &map.elevation.0,
+        &colors,
+        map.settings.0.elevation_threshold,
+        terrain_settings.height,
+    )
+    .to_mesh();
+
+    if let Ok(handle) = terrains.get_single() {
+        meshes.insert(&handle.0, terrain);
+        return;
+    }
+
+    commands.spawn((
+        TerrainView,
+        Mesh3d(meshes.add(terrain)),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            perceptual_roughness: 1.0,
+            ..default()
+        })),
+    ));
+}
+
+/// The parts of the map the terrain is built from.
+#[derive(SystemParam)]
+pub(super) struct TerrainInputs<'w> {
+    mesh: Res<'w, DualMesh>,
+    elevation: Res<'w, Elevation>,
+    biomes: Res<'w, Biomes>,
+    palette: Res<'w, BiomePalette>,
+    settings: Res<'w, GenerationSettings>,
+}
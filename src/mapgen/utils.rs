@@ -1,22 +1,39 @@
-use noise::{NoiseFn, Simplex};
-use voronoice::Point;
+use delaunator::Point;
 
-pub fn assign_elevation(points: &Vec<Point>, seed: u32) -> Vec<f64> {
-    let simplex = Simplex::new(seed);
-    let num_regions = points.len();
-    let mut elevation = Vec::new();
+/// Maps `elevation` so that the sea floor is at -1.0, sea level (`threshold`) at 0.0 and the
+/// highest peaks at 1.0, the range Mapgen4's water and climate models work in.
+pub fn sea_relative(elevation: f64, threshold: f64) -> f64 {
+    if elevation < threshold {
+        (elevation - threshold) / threshold
+    } else {
+        (elevation - threshold) / (1.0 - threshold)
+    }
+}
 
-    for i in 0..num_regions {
-        let nx = points[i].x / 25.0 - 1.0 / 2.0;
-        let ny = points[i].y / 25.0 - 1.0 / 2.0;
+/// Inverse of [`sea_relative`].
+pub fn from_sea_relative(value: f64, threshold: f64) -> f64 {
+    if value < 0.0 {
+        threshold + value * threshold
+    } else {
+        threshold + value * (1.0 - threshold)
+    }
+}
 
-        let noise = simplex.get([nx / 0.5, ny / 0.5]) / 2.0;
+/// Whether `(x, y)` lies inside the convex polygon `cell`, whichever way it winds.
+pub fn cell_contains(cell: &[Point], x: f64, y: f64) -> bool {
+    let mut sign = 0.0;
 
-        elevation.push(1.0 + noise);
+    for (i, a) in cell.iter().enumerate() {
+        let b = &cell[(i + 1) % cell.len()];
+        let cross = (b.x - a.x) * (y - a.y) - (b.y - a.y) * (x - a.x);
 
-        let d = 2.0 * f64::max(f64::abs(nx), f64::abs(ny));
-        elevation[i] = (1.0 + elevation[i] - d) / 2.0;
+        if cross != 0.0 {
+            if sign != 0.0 && cross.signum() != sign {
+                return false;
+            }
+            sign = cross.signum();
+        }
     }
 
-    elevation
-}
\ No newline at end of file
+    true
+}
@@ -1,4 +1,70 @@
-use bevy::ecs::event::Event;
+use bevy::{
+    ecs::event::Event,
+    prelude::{StateSet, States, SubStates},
+};
+use serde::{Deserialize, Serialize};
+
+/// Which demo the app is showing.
+#[derive(Default, States, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DemoState {
+    #[default]
+    Renderer,
+    Mapgen,
+}
 
 #[derive(Event, Default)]
-pub struct RegenCells;
\ No newline at end of file
+pub struct RegenCells;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// 16-bit heightmap and biome color images
+    Png,
+    /// Regions as polygons with their attributes, plus coastlines
+    GeoJson,
+    /// Vector image of the colored cells and coastlines
+    Svg,
+    /// Binary glTF of the map as a 3D terrain
+    Glb,
+}
+
+/// Requests the current map to be written to disk in the given format.
+#[derive(Event, Clone, Copy)]
+pub struct ExportMap(pub ExportFormat);
+
+/// On-disk encoding of a [`MapDocument`](crate::mapgen::MapDocument).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapFormat {
+    /// Human-readable, larger files
+    Json,
+    /// Compact bincode payload behind a short header
+    Binary,
+}
+
+/// Requests the current map to be saved as a document in the given format.
+#[derive(Event, Clone, Copy)]
+pub struct SaveMap(pub MapFormat);
+
+/// Requests the map document in the given format to replace the current map.
+#[derive(Event, Clone, Copy)]
+pub struct LoadMap(pub MapFormat);
+
+/// Steps back or forward through the history of map edits.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryStep {
+    Undo,
+    Redo,
+}
+
+/// How the map is shown. Only exists in the mapgen demo, and starts over as `Flat` each time
+/// the demo is entered.
+#[derive(SubStates, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[source(DemoState = DemoState::Mapgen)]
+pub enum MapView {
+    /// Top-down 2D map
+    #[default]
+    Flat,
+    /// Lit 3D terrain, navigated with the pan-orbit camera
+    Terrain,
+    /// Mapgen4-style oblique painterly map
+    Painterly,
+}
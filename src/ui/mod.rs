@@ -6,40 +6,74 @@ use std::{
 
 use bevy::{
     app::{Plugin, Update},
-    ecs::{entity::Entity, event::{Event, EventWriter}},
     log::{error, info, warn},
     prelude::{
-        in_state, on_event, AppExtStates, EventReader, IntoSystemConfigs, NextState, Res, ResMut,
-        Resource, State, StateTransitionEvent, States,
+        in_state, on_event, AppExtStates, Condition, DetectChangesMut, EventReader,
+        IntoSystemConfigs, NextState, Res, ResMut, Resource, State, StateTransitionEvent, States,
     },
     window::WindowCloseRequested,
 };
-use bevy_egui::{egui, EguiContexts};
+use bevy_inspector_egui::{bevy_egui::EguiContexts, egui};
+use renderer::{mapgen::MapgenSettings, state::DemoState};
 
-use crate::DemoState;
+use crate::demo::MAX_LIGHTS;
 
 #[derive(Default, States, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
-enum RendererState {
+pub enum RendererState {
     #[default]
     Basic,
     Toon,
     Pbr,
 }
 
-#[derive(Default, Resource, Serialize, Deserialize, Clone, Copy)]
+#[derive(Resource, Serialize, Deserialize, Clone, Copy)]
+#[serde(default)]
 pub struct MaterialSettings {
     pub color: [f32; 3],
+    /// Number of flat shades of the toon material
+    pub toon_bands: u32,
+    /// How far around the silhouette the toon outline reaches, from 0.0 to 1.0
+    pub outline_width: f32,
 }
 
-#[derive(Default, Resource, Serialize, Deserialize, Clone, Copy)]
+impl Default for MaterialSettings {
+    fn default() -> Self {
+        MaterialSettings {
+            color: [0.8, 0.3, 0.2],
+            toon_bands: 3,
+            outline_width: 0.25,
+        }
+    }
+}
+
+#[derive(Resource, Serialize, Deserialize, Clone)]
+#[serde(default)]
 pub struct LightSettings {
+    /// At most `MAX_LIGHTS`
+    pub lights: Vec<Light>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct Light {
     pub pos: [f32; 3],
     pub intensity: f32,
 }
 
-#[derive(Default, Resource, Serialize, Deserialize, Clone, Copy)]
-pub struct MapgenSettings {
-    pub num_cells: usize,
+impl Default for LightSettings {
+    fn default() -> Self {
+        LightSettings {
+            lights: vec![Light::default()],
+        }
+    }
+}
+
+impl Default for Light {
+    fn default() -> Self {
+        Light {
+            pos: [2.0, 2.0, 2.0],
+            intensity: 1.0,
+        }
+    }
 }
 
 #[derive(Default, Serialize, Deserialize)]
@@ -52,16 +86,12 @@ struct UIState {
     light: LightSettings,
 }
 
-#[derive(Event, Default)]
-pub struct NumCellsUpdated;
-
 pub struct UIPlugin;
 
 impl Plugin for UIPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
         // Init EGUI
         // app.add_plugins(EguiPlugin);
-        app.add_event::<NumCellsUpdated>();
         let file = File::open("ui_state.json");
 
         if let Ok(f) = file {
@@ -82,7 +112,6 @@ impl Plugin for UIPlugin {
                     warn!("Could not find UI State settings. Initializing with empty state");
                     app.init_resource::<MaterialSettings>();
                     app.init_resource::<LightSettings>();
-                    app.init_resource::<MapgenSettings>();
 
                     app.init_state::<DemoState>();
                     app.init_state::<RendererState>();
@@ -92,7 +121,6 @@ impl Plugin for UIPlugin {
             warn!("Could not open UI state settings. Initiaizing with empty state");
             app.init_resource::<MaterialSettings>();
             app.init_resource::<LightSettings>();
-            app.init_resource::<MapgenSettings>();
 
             app.init_state::<DemoState>();
             app.init_state::<RendererState>();
@@ -101,14 +129,16 @@ impl Plugin for UIPlugin {
         app.add_systems(
             Update,
             (
-                // spawn_ui.before(spawn_basic_ui),
+                spawn_ui.before(spawn_basic_ui),
                 log_transitions,
-                // (
-                //     spawn_light_ui,
-                //     spawn_basic_ui.run_if(in_state(RendererState::Basic)),
-                // )
-                //     .run_if(in_state(DemoState::Renderer)),
-                // (spawn_mapgen_ui).run_if(in_state(DemoState::Mapgen)),
+                (
+                    spawn_light_ui,
+                    spawn_basic_ui
+                        .run_if(in_state(RendererState::Basic).or(in_state(RendererState::Pbr))),
+                    spawn_toon_ui.run_if(in_state(RendererState::Toon)),
+                )
+                    .run_if(in_state(DemoState::Renderer)),
+                // The mapgen panel is the inspector in main.rs
                 save_ui_state.run_if(on_event::<WindowCloseRequested>),
             ),
         );
@@ -198,7 +228,7 @@ fn spawn_ui(
 
 fn spawn_basic_ui(mut egui_context: EguiContexts, mut material_settings: ResMut<MaterialSettings>) {
     if let Some(context) = egui_context.try_ctx_mut() {
-        egui::Window::new("Basic Renderer")
+        egui::Window::new("Material")
             .vscroll(false)
             .resizable(true)
             .show(context, |ui| {
@@ -209,46 +239,87 @@ fn spawn_basic_ui(mut egui_context: EguiContexts, mut material_settings: ResMut<
     }
 }
 
-fn spawn_light_ui(mut egui_context: EguiContexts, mut light: ResMut<LightSettings>) {
+fn spawn_toon_ui(mut egui_context: EguiContexts, mut material_settings: ResMut<MaterialSettings>) {
     if let Some(context) = egui_context.try_ctx_mut() {
-        egui::Window::new("Light Controls")
+        egui::Window::new("Toon Renderer")
             .vscroll(false)
             .resizable(true)
             .show(context, |ui| {
-                ui.label("Light");
+                let settings = material_settings.as_mut();
+
+                ui.label("Material");
+                ui.color_edit_button_rgb(&mut settings.color);
 
                 ui.horizontal(|ui| {
-                    ui.add(egui::DragValue::new(&mut light.pos[0]).speed(0.05));
-                    ui.add(egui::DragValue::new(&mut light.pos[1]).speed(0.05));
-                    ui.add(egui::DragValue::new(&mut light.pos[2]).speed(0.05));
+                    ui.label("Bands");
+                    ui.add(egui::DragValue::new(&mut settings.toon_bands).range(1..=8));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Outline");
+                    ui.add(
+                        egui::DragValue::new(&mut settings.outline_width)
+                            .speed(0.01)
+                            .range(0.0..=1.0),
+                    );
                 });
-
-                ui.add(
-                    egui::DragValue::new(&mut light.intensity)
-                        .speed(0.05)
-                        .range(0..=10),
-                );
             });
     }
 }
 
-fn spawn_mapgen_ui(mut egui_context: EguiContexts, mut mapgen_settings: ResMut<MapgenSettings>, mut events: EventWriter<NumCellsUpdated>) {
+fn spawn_light_ui(mut egui_context: EguiContexts, mut light: ResMut<LightSettings>) {
     if let Some(context) = egui_context.try_ctx_mut() {
-        egui::Window::new("Mapgen controls")
+        egui::Window::new("Light Controls")
             .vscroll(false)
             .resizable(true)
             .show(context, |ui| {
-                let settings = mapgen_settings.as_mut();
+                // Only flag the settings as changed when something was edited
+                let settings = light.bypass_change_detection();
+                let mut changed = false;
+                let mut removed = None;
 
-                ui.label("Num of cells");
+                for (i, light) in settings.lights.iter_mut().enumerate() {
+                    ui.label(format!("Light {}", i + 1));
 
-                ui.horizontal(|ui| {
-                    ui.add(egui::DragValue::new(&mut settings.num_cells));
+                    ui.horizontal(|ui| {
+                        for axis in light.pos.iter_mut() {
+                            changed |= ui.add(egui::DragValue::new(axis).speed(0.05)).changed();
+                        }
+                    });
 
-                    if ui.button("Regen cells").clicked() {
-                        events.send_default();
-                    }
-                })
+                    ui.horizontal(|ui| {
+                        changed |= ui
+                            .add(
+                                egui::DragValue::new(&mut light.intensity)
+                                    .speed(0.05)
+                                    .range(0..=10),
+                            )
+                            .changed();
+
+                        if ui.button("Remove").clicked() {
+                            removed = Some(i);
+                        }
+                    });
+                }
+
+                if let Some(i) = removed {
+                    settings.lights.remove(i);
+                    changed = true;
+                }
+
+                if ui
+                    .add_enabled(
+                        settings.lights.len() < MAX_LIGHTS,
+                        egui::Button::new("Add light"),
+                    )
+                    .clicked()
+                {
+                    settings.lights.push(Light::default());
+                    changed = true;
+                }
+
+                if changed {
+                    light.set_changed();
+                }
             });
     }
 }
@@ -266,8 +337,8 @@ fn save_ui_state(
         renderer: *renderer_state.get(), // rename to use the simple object builder
 
         material: *material_settings,
-        light: *light,
-        mapgen: *mapgen,
+        light: light.clone(),
+        mapgen: mapgen.clone(),
     };
 
     if let Ok(f) = file {
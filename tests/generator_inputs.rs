@@ -0,0 +1,89 @@
+//! Settings at the edges of what the inspector accepts must give a map or an error, never a
+//! panic.
+
+use renderer::mapgen::{generate_map, MapgenSettings, PointDistribution};
+
+const DISTRIBUTIONS: [PointDistribution; 4] = [
+    PointDistribution::JitteredGrid,
+    PointDistribution::PoissonDisc,
+    PointDistribution::HexGrid,
+    PointDistribution::Random,
+];
+
+#[test]
+fn out_of_range_settings_are_rejected() {
+    let cases = [
+        MapgenSettings {
+            grid_size: 0,
+            ..Default::default()
+        },
+        MapgenSettings {
+            grid_size: 1,
+            ..Default::default()
+        },
+        MapgenSettings {
+            jitter: f64::NAN,
+            ..Default::default()
+        },
+        MapgenSettings {
+            elevation_threshold: 0.0,
+            ..Default::default()
+        },
+        MapgenSettings {
+            elevation_threshold: 1.0,
+            ..Default::default()
+        },
+    ];
+
+    for settings in cases {
+        for point_distribution in DISTRIBUTIONS {
+            let settings = MapgenSettings {
+                point_distribution,
+                ..settings.clone()
+            };
+            assert!(generate_map(&settings).is_err());
+        }
+    }
+}
+
+#[test]
+fn smallest_grid_generates() {
+    for point_distribution in DISTRIBUTIONS {
+        let settings = MapgenSettings {
+            grid_size: 2,
+            point_distribution,
+            ..Default::default()
+        };
+
+        let map = generate_map(&settings).unwrap();
+        assert!(map.regions.len() >= 3, "{point_distribution:?}");
+    }
+}
+
+#[test]
+fn large_jitter_keeps_every_site() {
+    for grid_size in 2..=20 {
+        for jitter in [-3.0, 1.0, 3.0, 5.0, 10.0, 1e9] {
+            for rng_seed in 0..4 {
+                let settings = MapgenSettings {
+                    rng_seed,
+                    grid_size,
+                    jitter,
+                    ..Default::default()
+                };
+                let map = generate_map(&settings).unwrap();
+                assert_eq!(
+                    map.regions.len(),
+                    grid_size * grid_size,
+                    "{grid_size} {jitter}"
+                );
+
+                let settings = MapgenSettings {
+                    point_distribution: PointDistribution::HexGrid,
+                    ..settings
+                };
+                assert!(generate_map(&settings).is_ok(), "{grid_size} {jitter}");
+            }
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//! Golden tests pinning the output of the generator for fixed settings.
+//!
+//! Every case is compared against `tests/fixtures/<name>.json`. A dependency bump that changes
+//! the points, the cells or the elevation of any map fails here instead of silently reshaping
+//! every saved seed.
+//!
+//! When a change is intended, rewrite the fixtures and review the diff before committing it:
+//!
+//! ```text
+//! MAPGEN_BLESS=1 cargo test --test generator_snapshots
+//! ```
+//!
+//! A missing fixture fails the test like any other mismatch; only `MAPGEN_BLESS` writes them.
+
+use std::{env, fs, path::PathBuf};
+
+use renderer::mapgen::{
+    generate_map, FractalType, GeneratedMap, IslandShape, MapgenSettings, NoiseType,
+    PointDistribution,
+};
+use serde::{Deserialize, Serialize};
+
+/// Points are compared with a tolerance, so that only real drift fails the test and not
+/// rounding that differs between platforms.
+const POINT_TOLERANCE: f64 = 1e-9;
+
+/// Elevation is quantized to this step before hashing, so only real drift changes the hash.
+const ELEVATION_STEP: f64 = 1e-9;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Snapshot {
+    regions: usize,
+    /// Number of vertices of each region's cell
+    cell_vertices: Vec<usize>,
+    points: Vec<[f64; 2]>,
+    /// FNV-1a of the quantized elevation, as hex
+    elevation_hash: String,
+}
+
+impl Snapshot {
+    fn new(map: &GeneratedMap) -> Self {
+        Snapshot {
+            regions: map.regions.len(),
+            cell_vertices: map.regions.cells.iter().map(Vec::len).collect(),
+            points: map.regions.points.iter().map(|p| [p.x, p.y]).collect(),
+            elevation_hash: format!("{:016x}", elevation_hash(&map.elevation)),
+        }
+    }
+}
+
+/// FNV-1a, spelled out because `DefaultHasher` may change between Rust releases.
+fn elevation_hash(elevation: &[f64]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+
+    for &e in elevation {
+        let quantized = (e / ELEVATION_STEP).round() as i64;
+
+        for byte in quantized.to_le_bytes() {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    hash
+}
+
+fn fixture_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(format!("{name}.json"))
+}
+
+fn write_fixture(name: &str, snapshot: &Snapshot) {
+    let path = fixture_path(name);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+
+    let json = serde_json::to_string_pretty(snapshot).unwrap();
+    fs::write(&path, json + "\n").unwrap();
+}
+
+fn assert_snapshot(name: &str, settings: &MapgenSettings) {
+    let actual = Snapshot::new(&generate_map(settings).unwrap());
+
+    // The same settings must give the same map within a single build, fixtures or not
+    assert_eq!(
+        actual,
+        Snapshot::new(&generate_map(settings).unwrap()),
+        "{name}: generating twice with the same settings gave different maps"
+    );
+
+    if env::var_os("MAPGEN_BLESS").is_some() {
+        write_fixture(name, &actual);
+        return;
+    }
+
+    let path = fixture_path(name);
+    let Ok(json) = fs::read_to_string(&path) else {
+        panic!(
+            "{name}: missing fixture {}, run with MAPGEN_BLESS=1 to create it",
+            path.display()
+        );
+    };
+    let expected: Snapshot = serde_json::from_str(&json).unwrap();
+
+    let bless = "rerun with MAPGEN_BLESS=1 if the change is intended";
+
+    assert_eq!(
+        actual.regions, expected.regions,
+        "{name}: region count drifted, {bless}"
+    );
+    assert_eq!(
+        actual.cell_vertices, expected.cell_vertices,
+        "{name}: cell shapes drifted, {bless}"
+    );
+
+    for (r, (a, e)) in actual.points.iter().zip(&expected.points).enumerate() {
+        let distance = f64::hypot(a[0] - e[0], a[1] - e[1]);
+        assert!(
+            distance <= POINT_TOLERANCE,
+            "{name}: point {r} drifted from {e:?} to {a:?}, {bless}"
+        );
+    }
+
+    assert_eq!(
+        actual.elevation_hash, expected.elevation_hash,
+        "{name}: elevation drifted, {bless}"
+    );
+}
+
+#[test]
+fn default_settings() {
+    assert_snapshot("default", &MapgenSettings::default());
+}
+
+#[test]
+fn poisson_disc_ridged_radial() {
+    let mut settings = MapgenSettings {
+        rng_seed: 42,
+        grid_size: 16,
+        point_distribution: PointDistribution::PoissonDisc,
+        ..Default::default()
+    };
+    settings.elevation.fractal = FractalType::Ridged;
+    settings.elevation.octaves = 4;
+    settings.elevation.island_shape = IslandShape::Radial;
+
+    assert_snapshot("poisson_disc_ridged_radial", &settings);
+}
+
+#[test]
+fn hex_grid_unrelaxed_perlin_billow() {
+    let mut settings = MapgenSettings {
+        rng_seed: 7,
+        grid_size: 12,
+        point_distribution: PointDistribution::HexGrid,
+        jitter: 0.3,
+        relax_points: false,
+        ..Default::default()
+    };
+    settings.elevation.noise = NoiseType::Perlin;
+    settings.elevation.fractal = FractalType::Billow;
+    settings.elevation.octaves = 3;
+
+    assert_snapshot("hex_grid_unrelaxed_perlin_billow", &settings);
+}
+
+#[test]
+fn random_continent() {
+    let mut settings = MapgenSettings {
+        rng_seed: 1234,
+        grid_size: 10,
+        point_distribution: PointDistribution::Random,
+        lloyd_iterations: 5,
+        ..Default::default()
+    };
+    settings.elevation.noise = NoiseType::OpenSimplex;
+    settings.elevation.island_shape = IslandShape::Continent;
+
+    assert_snapshot("random_continent", &settings);
+}
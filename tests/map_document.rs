@@ -0,0 +1,71 @@
+//! Saved maps must come back exactly as they were, and broken documents must be rejected
+//! instead of panicking while the Voronoi diagram is built.
+
+use renderer::mapgen::{generate_map, GeneratedMap, MapDocument, MapgenSettings};
+
+fn document() -> (MapgenSettings, GeneratedMap, MapDocument) {
+    let settings = MapgenSettings::default();
+    let map = generate_map(&settings).unwrap();
+    let document = MapDocument::new(&settings, &map.regions, &map.elevation);
+
+    (settings, map, document)
+}
+
+fn assert_restores(settings: &MapgenSettings, map: &GeneratedMap, decoded: MapDocument) {
+    assert!(decoded.settings == *settings);
+
+    let restored = decoded.restore().unwrap();
+    assert_eq!(restored.regions.points.len(), map.regions.points.len());
+
+    // Exact comparisons, a float that doesn't survive the round trip moves the whole map
+    for (restored, saved) in restored.regions.points.iter().zip(&map.regions.points) {
+        assert_eq!((restored.x, restored.y), (saved.x, saved.y));
+    }
+    assert_eq!(restored.elevation, map.elevation);
+    assert_eq!(restored.moisture, map.moisture);
+}
+
+#[test]
+fn json_round_trip() {
+    let (settings, map, document) = document();
+
+    let decoded = MapDocument::from_json(&document.to_json().unwrap()).unwrap();
+    assert_restores(&settings, &map, decoded);
+}
+
+#[test]
+fn binary_round_trip() {
+    let (settings, map, document) = document();
+
+    let decoded = MapDocument::from_bytes(&document.to_bytes().unwrap()).unwrap();
+    assert_restores(&settings, &map, decoded);
+}
+
+#[test]
+fn duplicate_points_are_rejected() {
+    let (_, _, mut document) = document();
+    document.points[1] = document.points[0];
+
+    assert!(document.restore().is_err());
+}
+
+#[test]
+fn non_finite_points_are_rejected() {
+    let (_, _, mut document) = document();
+    document.points[0][1] = f64::NAN;
+
+    assert!(document.restore().is_err());
+}
+
+#[test]
+fn out_of_range_settings_are_rejected() {
+    let (_, _, document) = document();
+
+    let mut zero_threshold = document.clone();
+    zero_threshold.settings.elevation_threshold = 0.0;
+    assert!(zero_threshold.restore().is_err());
+
+    let mut empty_grid = document;
+    empty_grid.settings.grid_size = 0;
+    assert!(empty_grid.restore().is_err());
+}
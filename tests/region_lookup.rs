@@ -0,0 +1,58 @@
+//! The neighbour walks behind picking and the brush must agree with a scan over every region.
+
+use renderer::mapgen::{generate_map, map_bounds, MapgenSettings, PointDistribution, Regions};
+
+fn distance(regions: &Regions, r: usize, x: f64, y: f64) -> f64 {
+    f64::hypot(regions.points[r].x - x, regions.points[r].y - y)
+}
+
+/// Points spread over the whole map, including its edges and corners.
+fn samples(grid_size: usize) -> Vec<(f64, f64)> {
+    let bounds = map_bounds(grid_size);
+    let steps = 4 * grid_size;
+
+    (0..=steps)
+        .flat_map(|i| (0..=steps).map(move |j| (i, j)))
+        .map(|(i, j)| {
+            let (u, v) = (i as f64 / steps as f64, j as f64 / steps as f64);
+            (
+                bounds.left() + u * bounds.width(),
+                bounds.top() + v * bounds.height(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn walks_match_a_full_scan() {
+    for point_distribution in [
+        PointDistribution::JitteredGrid,
+        PointDistribution::PoissonDisc,
+        PointDistribution::HexGrid,
+        PointDistribution::Random,
+    ] {
+        let settings = MapgenSettings {
+            grid_size: 12,
+            point_distribution,
+            ..Default::default()
+        };
+        let regions = generate_map(&settings).unwrap().regions;
+
+        for (k, (x, y)) in samples(settings.grid_size).into_iter().enumerate() {
+            let start = k % regions.len();
+
+            let nearest = regions.nearest(x, y, start).unwrap();
+            let closest = (0..regions.len())
+                .map(|r| distance(&regions, r, x, y))
+                .fold(f64::INFINITY, f64::min);
+            assert_eq!(distance(&regions, nearest, x, y), closest, "({x}, {y})");
+
+            let mut within = regions.within(x, y, 2.5, start);
+            within.sort_unstable();
+            let scanned: Vec<_> = (0..regions.len())
+                .filter(|&r| distance(&regions, r, x, y) < 2.5)
+                .collect();
+            assert_eq!(within, scanned, "({x}, {y})");
+        }
+    }
+}